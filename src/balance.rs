@@ -0,0 +1,108 @@
+use crate::prelude::*;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/*
+Plays a batch of seeded games with the bot and prints statistics about each one, for tuning monsters and traps
+from data instead of gut feeling, e.g.
+    cargo run --release -- --balance 1 500 > balance.csv
+    cargo run --release -- --balance 1 500 --json > balance.json
+The games are the same ones --soak plays. Games that go wrong are left out of the statistics and listed on stderr.
+ */
+pub fn balance_from_args(args: &[String]) -> BError {
+    let json = args.iter().any(|arg| arg == "--json");
+    let mut numbers = args.iter().filter(|arg| *arg != "--json");
+    let first_seed = numbers.next().ok_or("--balance needs a first seed")?.parse::<u64>()?;
+    let count = numbers.next().map_or(Ok(100), |count| count.parse::<u64>())?;
+
+    let mut reports = Vec::new();
+    let mut problems = 0;
+    for seed in first_seed .. first_seed + count {
+        match soak_game(seed) {
+            Ok(game) => reports.push(GameReport::new(seed, game)),
+            Err(problem) => {
+                problems += 1;
+                eprintln!("seed {seed}: {problem}");
+            }
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        print!("{}", csv(&reports));
+    }
+    eprintln!("Played {count} games, {problems} left out with problems");
+    Ok(())
+}
+
+/*
+How one game went. Damage taken is listed for every monster template, even those that never drew blood,
+followed by every other source that hurt the player.
+ */
+#[derive(Serialize)]
+struct GameReport {
+    seed: u64,
+    turns: u32,
+    depth: u32,
+    level: i32,
+    died: bool,
+    cause_of_death: Option<String>,
+    items_used: BTreeMap<String, u32>,
+    damage_taken: BTreeMap<String, i32>,
+}
+
+impl GameReport {
+    fn new(seed: u64, game: SoakGame) -> Self {
+        let mut damage_taken = game.stats.damage_taken;
+        for monster in &MONSTERS {
+            damage_taken.entry(monster.name.to_string()).or_default();
+        }
+        Self {
+            seed,
+            turns: game.stats.turns,
+            depth: game.depth,
+            level: game.level,
+            died: game.died,
+            cause_of_death: game.stats.killed_by.filter(|_| game.died),
+            items_used: game.stats.items_used,
+            damage_taken,
+        }
+    }
+}
+
+/*
+One row per game. Items used are totalled; damage gets a column per source, e.g. damage_ettin or damage_dart_trap,
+with the monster templates first, in the order they are spawned from.
+ */
+fn csv(reports: &[GameReport]) -> String {
+    let others: BTreeSet<&String> = reports
+        .iter()
+        .flat_map(|report| report.damage_taken.keys())
+        .filter(|source| !MONSTERS.iter().any(|monster| monster.name == source.as_str()))
+        .collect();
+    let sources: Vec<&str> =
+        MONSTERS.iter().map(|monster| monster.name).chain(others.into_iter().map(String::as_str)).collect();
+
+    let mut header = vec!["seed", "turns", "depth", "level", "died", "cause_of_death", "items_used"]
+        .into_iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    header.extend(sources.iter().map(|source| format!("damage_{}", source.to_lowercase().replace(' ', "_"))));
+
+    let mut text = header.join(",") + "\n";
+    for report in reports {
+        let mut row = vec![
+            report.seed.to_string(),
+            report.turns.to_string(),
+            report.depth.to_string(),
+            report.level.to_string(),
+            report.died.to_string(),
+            report.cause_of_death.clone().unwrap_or_default(),
+            report.items_used.values().sum::<u32>().to_string(),
+        ];
+        row.extend(sources.iter().map(|source| report.damage_taken.get(*source).copied().unwrap_or(0).to_string()));
+        text += &(row.join(",") + "\n");
+    }
+    text
+}
@@ -0,0 +1,197 @@
+use crate::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+// Extra steps the bot is willing to walk to keep out of harm's way.
+const TRAP_PENALTY: i32 = 10;
+const LAVA_PENALTY: i32 = 30;
+
+/*
+A player that plays by itself, for soak testing. It only knows what the player knows: the tiles revealed so far,
+the enemies in sight and the traps already found. Each turn it picks the first thing on this list it can reach:
+    an enemy in sight, to fight it (walking into an enemy attacks it);
+    food it has seen, once the player is no longer well fed, to eat it;
+    where it last saw an enemy;
+    the stairs down, or a chasm, once it has seen one;
+    the nearest revealed tile next to unexplored ones.
+Remembering where the last enemy was keeps the bot from dithering when an enemy drops in and out of sight.
+When the player goes up a level, the bot takes whichever perk the level-up menu offers first.
+ */
+#[derive(Default)]
+pub struct Bot {
+    last_seen_enemy: Option<Point>,
+    map_level: u32,
+}
+
+impl Bot {
+    /*
+    None means there is nothing left to explore and no known way down.
+     */
+    pub fn choose_action(&mut self, ecs: &World, resources: &Resources) -> Option<Action> {
+        let map = resources.get::<Map>().unwrap();
+        let lighting = resources.get::<Lighting>().unwrap();
+        let settings = resources.get::<Settings>().unwrap();
+
+        let mut player = <(&Point, &FieldOfView, &Player)>::query();
+        let (start, fov, player) = player.iter(ecs).next()?;
+        let mut experience = <&Experience>::query().filter(component::<Player>());
+        if experience.iter(ecs).next().is_some_and(|experience| experience.perks_to_pick > 0) {
+            return Some(Action::Wait);
+        }
+        if player.map_level != self.map_level || self.last_seen_enemy == Some(*start) {
+            self.map_level = player.map_level;
+            self.last_seen_enemy = None;
+        }
+        if map.tile(*start) == Some(TileType::Exit) {
+            return Some(Action::Descend);
+        }
+        let mut hunger = <&Hunger>::query().filter(component::<Player>());
+        let hungry = hunger.iter(ecs).next().is_some_and(|hunger| hunger.state() != HungerState::WellFed);
+        let foods: HashSet<Point> = <&Point>::query()
+            .filter(component::<Food>())
+            .iter(ecs)
+            .filter(|pos| hungry && map.try_idx(**pos).is_some_and(|idx| map.revealed_tiles[idx]))
+            .copied()
+            .collect();
+        if foods.contains(start) {
+            return Some(Action::Eat);
+        }
+
+        let traps: HashSet<Point> = <&Point>::query()
+            .filter(component::<Trap>() & !component::<Hidden>())
+            .iter(ecs)
+            .copied()
+            .collect();
+        let enemies: Vec<Point> = <&Point>::query()
+            .filter(component::<Enemy>())
+            .iter(ecs)
+            .filter(|pos| fov.visible_tiles.contains(pos) && lighting.is_lit(&map, **pos))
+            .copied()
+            .collect();
+
+        let paths = Paths::search(&map, *start, &traps, settings.block_diagonal_corners);
+        let ways_down = |idx: usize| matches!(map.tiles[idx], TileType::Exit | TileType::Chasm);
+        let frontier = |idx: usize| {
+            let pos = map.index_to_point2d(idx);
+            !ways_down(idx)
+                && neighbors(pos).any(|n| map.try_idx(n).is_some_and(|n| !map.revealed_tiles[n]))
+        };
+
+        let enemy = paths.nearest(|idx| enemies.contains(&map.index_to_point2d(idx)));
+        if let Some(enemy) = enemy {
+            self.last_seen_enemy = Some(map.index_to_point2d(enemy));
+        }
+        let target = enemy
+            .or_else(|| paths.nearest(|idx| foods.contains(&map.index_to_point2d(idx))))
+            .or_else(|| paths.nearest(|idx| self.last_seen_enemy == Some(map.index_to_point2d(idx))))
+            .or_else(|| paths.nearest(ways_down))
+            .or_else(|| paths.nearest(frontier))?;
+        let step = map.index_to_point2d(paths.first_step(target));
+        direction(step - *start)
+    }
+}
+
+/*
+Cheapest known routes from the player to every revealed tile it can walk to.
+Closed doors cost an extra turn to open and deep water slows the player down; lava and found traps are avoided
+unless there is no other way. Chasms and the stairs end a route, since the player leaves the level there.
+ */
+struct Paths {
+    start: usize,
+    cost: Vec<Option<i32>>,
+    came_from: Vec<usize>,
+}
+
+impl Paths {
+    fn search(map: &Map, start: Point, traps: &HashSet<Point>, block_corners: bool) -> Self {
+        let start_idx = map.map_idx(start.x, start.y);
+        let mut paths = Self {
+            start: start_idx,
+            cost: vec![None; map.tiles.len()],
+            came_from: vec![start_idx; map.tiles.len()],
+        };
+        paths.cost[start_idx] = Some(0);
+
+        let mut open = BinaryHeap::from([Reverse((0, start_idx))]);
+        while let Some(Reverse((cost, idx))) = open.pop() {
+            if paths.cost[idx].is_some_and(|known| known < cost) {
+                continue;
+            }
+            if idx != start_idx && matches!(map.tiles[idx], TileType::Exit | TileType::Chasm) {
+                continue;
+            }
+            let pos = map.index_to_point2d(idx);
+            for next in neighbors(pos) {
+                let Some(next_idx) = map.try_idx(next).filter(|n| map.revealed_tiles[*n]) else {
+                    continue;
+                };
+                if block_corners && map.cuts_corner(pos, next) {
+                    continue;
+                }
+                let Some(step) = step_cost(map.tiles[next_idx]) else {
+                    continue;
+                };
+                let step = if traps.contains(&next) { step + TRAP_PENALTY } else { step };
+                let next_cost = cost + step;
+                if paths.cost[next_idx].is_none_or(|known| next_cost < known) {
+                    paths.cost[next_idx] = Some(next_cost);
+                    paths.came_from[next_idx] = idx;
+                    open.push(Reverse((next_cost, next_idx)));
+                }
+            }
+        }
+        paths
+    }
+
+    fn nearest(&self, wanted: impl Fn(usize) -> bool) -> Option<usize> {
+        self.cost
+            .iter()
+            .enumerate()
+            .filter(|(idx, cost)| cost.is_some() && *idx != self.start && wanted(*idx))
+            .min_by_key(|(_, cost)| *cost)
+            .map(|(idx, _)| idx)
+    }
+
+    // The tile to step onto first on the way to the target.
+    fn first_step(&self, target: usize) -> usize {
+        let mut idx = target;
+        while self.came_from[idx] != self.start {
+            idx = self.came_from[idx];
+        }
+        idx
+    }
+}
+
+/*
+How many turns the bot reckons a tile is worth walking onto, or None if it can't be walked onto at all.
+ */
+fn step_cost(tile: TileType) -> Option<i32> {
+    match tile {
+        TileType::Floor | TileType::Door { open: true } | TileType::Exit | TileType::Chasm => Some(1),
+        TileType::Door { open: false } | TileType::DeepWater => Some(2),
+        TileType::Lava => Some(LAVA_PENALTY),
+        TileType::Wall => None,
+    }
+}
+
+fn neighbors(pos: Point) -> impl Iterator<Item = Point> {
+    (-1 ..= 1)
+        .flat_map(|dy| (-1 ..= 1).map(move |dx| Point::new(dx, dy)))
+        .filter(|delta| *delta != Point::zero())
+        .map(move |delta| pos + delta)
+}
+
+fn direction(delta: Point) -> Option<Action> {
+    [
+        Action::MoveNorth,
+        Action::MoveSouth,
+        Action::MoveEast,
+        Action::MoveWest,
+        Action::MoveNorthEast,
+        Action::MoveNorthWest,
+        Action::MoveSouthEast,
+        Action::MoveSouthWest,
+    ]
+    .into_iter()
+    .find(|action| action.delta() == Some(delta))
+}
@@ -5,6 +5,10 @@ pub struct Camera {
     pub right_x: i32,
     pub top_y: i32,
     pub bottom_y: i32,
+    // How close (in tiles) the player may get to the edge of the view before it scrolls. None keeps the player centered.
+    pub dead_zone: Option<i32>,
+    // While free-looking, movement keys pan the camera instead of moving the player.
+    pub free_look: bool,
 }
 
 /*
@@ -12,22 +16,134 @@ The Camera also becomes a resource.
  */
 impl Camera {
     /*
-    The new and on_player_move functions are essentially the same:
-        they define the visible window as centered on the player
+    The camera starts centered on the player, but never shows more than it has to of the area outside the map.
      */
-    pub fn new(player_position: Point)-> Self {
-        Self {
-            left_x : player_position.x - DISPLAY_WIDTH/2,
-            right_x : player_position.x + DISPLAY_WIDTH/2,
-            top_y : player_position.y - DISPLAY_HEIGHT/2,
-            bottom_y : player_position.y + DISPLAY_HEIGHT/2
+    pub fn new(player_position: Point, map: &Map, dead_zone: Option<i32>)-> Self {
+        let mut camera = Self {
+            left_x: 0,
+            right_x: 0,
+            top_y: 0,
+            bottom_y: 0,
+            dead_zone,
+            free_look: false,
+        };
+        camera.look_at(player_position, map);
+        camera
+    }
+
+    /*
+    Without a dead zone this keeps the player in the middle of the screen.
+    With one, the view only scrolls far enough to keep the player dead_zone tiles away from its edge.
+     */
+    pub fn on_player_move(&mut self, player_position: Point, map: &Map) {
+        let Some(margin) = self.dead_zone else {
+            self.look_at(player_position, map);
+            return;
+        };
+
+        // A margin reaching the middle of the view would scroll it back and forth every turn, even with the player standing still.
+        let margin_x = i32::min(margin, DISPLAY_WIDTH / 2 - 1);
+        let margin_y = i32::min(margin, DISPLAY_HEIGHT / 2 - 1);
+        let mut left_x = self.left_x;
+        let mut top_y = self.top_y;
+
+        if player_position.x < left_x + margin_x {
+            left_x = player_position.x - margin_x;
+        } else if player_position.x >= left_x + DISPLAY_WIDTH - margin_x {
+            left_x = player_position.x + margin_x + 1 - DISPLAY_WIDTH;
+        }
+        if player_position.y < top_y + margin_y {
+            top_y = player_position.y - margin_y;
+        } else if player_position.y >= top_y + DISPLAY_HEIGHT - margin_y {
+            top_y = player_position.y + margin_y + 1 - DISPLAY_HEIGHT;
         }
+
+        self.move_to(left_x, top_y, map);
+    }
+
+    /*
+    Centers the view on a point, as far as the map edges allow.
+     */
+    pub fn look_at(&mut self, position: Point, map: &Map) {
+        self.move_to(position.x - DISPLAY_WIDTH / 2, position.y - DISPLAY_HEIGHT / 2, map);
     }
 
-    pub fn on_player_move(&mut self, player_position: Point) {
-        self.left_x = player_position.x - DISPLAY_WIDTH/2;
-        self.right_x = player_position.x + DISPLAY_WIDTH/2;
-        self.top_y = player_position.y - DISPLAY_HEIGHT/2;
-        self.bottom_y = player_position.y + DISPLAY_HEIGHT/2;
+    /*
+    Scrolls the view by delta tiles, used by free-look.
+     */
+    pub fn pan(&mut self, delta: Point, map: &Map) {
+        self.move_to(self.left_x + delta.x, self.top_y + delta.y, map);
     }
-}
\ No newline at end of file
+
+    fn move_to(&mut self, left_x: i32, top_y: i32, map: &Map) {
+        let size = map.dimensions();
+        self.left_x = clamp_to_map(left_x, DISPLAY_WIDTH, size.x);
+        self.right_x = self.left_x + DISPLAY_WIDTH;
+        self.top_y = clamp_to_map(top_y, DISPLAY_HEIGHT, size.y);
+        self.bottom_y = self.top_y + DISPLAY_HEIGHT;
+    }
+}
+
+/*
+Keeps a view of view_size tiles inside a map of map_size tiles along one axis.
+A map smaller than the view is centered in it instead.
+ */
+fn clamp_to_map(start: i32, view_size: i32, map_size: i32) -> i32 {
+    if map_size <= view_size {
+        (map_size - view_size) / 2
+    } else {
+        start.clamp(0, map_size - view_size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_view_stops_at_the_map_edges() {
+        let map = Map::new(80, 50);
+        let size = map.dimensions();
+        let camera = Camera::new(Point::new(1, 1), &map, None);
+        assert_eq!((camera.left_x, camera.top_y), (0, 0));
+        let camera = Camera::new(size - Point::new(1, 1), &map, None);
+        assert_eq!((camera.right_x, camera.bottom_y), (size.x, size.y));
+    }
+
+    #[test]
+    fn the_dead_zone_only_scrolls_near_the_edge_of_the_view() {
+        let map = Map::new(80, 50);
+        let size = map.dimensions();
+        let start = Point::new(size.x / 2, size.y / 2);
+        let mut camera = Camera::new(start, &map, Some(4));
+        let left_x = camera.left_x;
+
+        camera.on_player_move(start + Point::new(3, 0), &map);
+        assert_eq!(camera.left_x, left_x);
+
+        let near_edge = Point::new(camera.right_x - 4, start.y);
+        camera.on_player_move(near_edge, &map);
+        assert_eq!(camera.right_x, near_edge.x + 5);
+    }
+
+    #[test]
+    fn a_map_smaller_than_the_view_is_centered_in_it() {
+        let map = Map::new(20, 10);
+        let camera = Camera::new(Point::new(5, 5), &map, Some(4));
+        assert_eq!(camera.left_x, (20 - DISPLAY_WIDTH) / 2);
+        assert_eq!(camera.top_y, (10 - DISPLAY_HEIGHT) / 2);
+    }
+
+    #[test]
+    fn a_dead_zone_as_wide_as_the_view_holds_still_while_the_player_does() {
+        let map = Map::new(200, 200);
+        let player = Point::new(100, 100);
+        let mut camera = Camera::new(player, &map, Some(DISPLAY_WIDTH));
+        camera.on_player_move(player, &map);
+        let view = (camera.left_x, camera.top_y);
+        for _ in 0 .. 3 {
+            camera.on_player_move(player, &map);
+            assert_eq!((camera.left_x, camera.top_y), view);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 pub use crate::prelude::*;
+use std::collections::HashSet;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Render {
@@ -9,13 +10,437 @@ pub struct Render {
 }
 
 /*
-Hero Tag Component
+Hero Component. map_level counts how many levels deep the player has descended.
  */
 #[derive(Clone, Copy, Debug, PartialEq)]
-pub struct Player;
+pub struct Player {
+    pub map_level: u32,
+}
 
 /*
 Enemy tag component.
  */
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Enemy;
+
+
+/*
+Tag for entities that stand in the way of anything else trying to enter their tile.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BlocksTile;
+
+/*
+What a monster knows about the player, and so what it does on its turn.
+    Monsters only learn where the player is by seeing them in their own field of view, or by hearing a noise.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alertness {
+    // Doesn't move, or see anything, until a noise wakes it up.
+    Asleep,
+    // Wanders around aimlessly.
+    Wandering,
+    // Can see the player at target, and goes straight for them.
+    Chasing { target: Point },
+    // Lost sight of the player, or heard something, at target. It goes to look there,
+    // and gives up after turns more turns.
+    Searching { target: Point, turns: i32 },
+}
+
+/*
+A trap, lying in wait for anything that steps on its tile.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Trap {
+    pub kind: TrapKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TrapKind {
+    // Shoots a dart at whoever stepped on it.
+    Dart,
+    // Moves its victim to a random spot on the level.
+    Teleport,
+    // Rings loudly enough to wake every monster nearby.
+    Alarm,
+    // Drops its victim, bruised, to the next level.
+    Pit,
+}
+
+/*
+Tag for entities the player doesn't know about yet, like an undetected trap. They aren't drawn.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hidden;
+
+/*
+Tag for anything that can swim through deep water. Everything else has to walk around it.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Swimmer;
+
+/*
+An entity still busy with a slow action, like wading through deep water.
+    It sits out this many more turns before it can act again.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Busy {
+    pub turns: i32,
+}
+
+/*
+Tag for anything able to open a closed door by walking into it.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OpensDoors;
+
+/*
+Message component: an entity asking to move to destination.
+    Messages live on their own entities, and the movement system deletes them once they've been handled.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToMove {
+    pub entity: Entity,
+    pub destination: Point,
+}
+
+/*
+Message component: an entity has just stepped onto destination.
+    The movement system sends it after every successful move, for systems that react to where things walk.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnteredTile {
+    pub entity: Entity,
+    pub destination: Point,
+}
+
+/*
+What an entity is called, for anything that describes it to the player.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Name(pub String);
+
+/*
+Hit points for anything that can be hurt. current is what is left, max is what it heals back up to.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    pub current: i32,
+    pub max: i32,
+}
+
+/*
+The set of tiles an entity can currently see.
+    is_dirty marks the set as stale, so the fov system only recalculates it after the entity moved.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldOfView {
+    pub visible_tiles: HashSet<Point>,
+    pub radius: i32,
+    pub is_dirty: bool,
+}
+
+impl FieldOfView {
+    pub fn new(radius: i32) -> Self {
+        Self {
+            visible_tiles: HashSet::new(),
+            radius,
+            is_dirty: true,
+        }
+    }
+
+    /*
+    A copy with no visible tiles that the fov system will fill in again.
+     */
+    pub fn clone_dirty(&self) -> Self {
+        Self::new(self.radius)
+    }
+}
+
+/*
+Anything that gives off light: wall torches, the player's lantern, monsters that glow.
+    Light fades with distance and stops at anything opaque, so it can't shine through walls or closed doors.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LightSource {
+    pub radius: i32,
+    pub color: RGB,
+}
+
+/*
+How many hit points a blow from this entity takes from whatever it hits.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Damage(pub i32);
+
+/*
+Message component: attacker is hitting victim, for damage hit points. The combat system deletes it once the blow has landed.
+spell is the index in the spellbook of the spell the blow comes from, if it isn't a plain blow.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToAttack {
+    pub attacker: Entity,
+    pub victim: Entity,
+    pub damage: i32,
+    pub spell: Option<usize>,
+}
+
+/*
+A bow, sling or anything else that can hit what it's aimed at from afar, up to range tiles away.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RangedWeapon {
+    pub damage: i32,
+    pub range: i32,
+}
+
+/*
+Message component: shooter is firing its ranged weapon at target. The ranged system deletes it once the shot has landed.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToFire {
+    pub shooter: Entity,
+    pub target: Point,
+}
+
+/*
+Magic power for casting spells. current is what is left, max is what resting restores it up to.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mana {
+    pub current: i32,
+    pub max: i32,
+}
+
+/*
+A status effect that wears off by itself. The status_effects system takes a turn off it at the end of every turn
+and removes it once it has run out. NAME is what the HUD and the tooltips call it.
+ */
+pub trait StatusEffect: legion::storage::Component {
+    const NAME: &'static str;
+    fn turns(&self) -> i32;
+    fn turns_mut(&mut self) -> &mut i32;
+}
+
+/*
+Poison takes damage hit points at the end of every turn, for turns more turns.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Poisoned {
+    pub damage: i32,
+    pub turns: i32,
+}
+
+/*
+A confused creature can't tell which way it is going, or who it is fighting.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Confused {
+    pub turns: i32,
+}
+
+/*
+A hasted creature gains energy twice as fast, so it acts twice as often.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hasted {
+    pub turns: i32,
+}
+
+/*
+A slowed creature gains energy half as fast, so it acts half as often.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slowed {
+    pub turns: i32,
+}
+
+macro_rules! status_effects {
+    ($($effect:ident: $name:literal),* $(,)?) => {
+        $(impl StatusEffect for $effect {
+            const NAME: &'static str = $name;
+            fn turns(&self) -> i32 {
+                self.turns
+            }
+            fn turns_mut(&mut self) -> &mut i32 {
+                &mut self.turns
+            }
+        })*
+
+        /*
+        The status effects on an entity, with the turns each one has left, e.g. "Poisoned (3)".
+        Systems calling this need read access to every status effect.
+         */
+        pub fn describe_status_effects(entry: &legion::world::EntryRef) -> Vec<String> {
+            let mut effects = Vec::new();
+            $(if let Ok(effect) = entry.get_component::<$effect>() {
+                effects.push(format!("{} ({})", $effect::NAME, effect.turns()));
+            })*
+            effects
+        }
+    };
+}
+
+status_effects!(Poisoned: "Poisoned", Confused: "Confused", Hasted: "Hasted", Slowed: "Slowed");
+
+/*
+Message component: caster is casting the spell with this index in the Spellbook at target.
+    The spells system deletes it once the spell has taken effect.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToCast {
+    pub caster: Entity,
+    pub spell: usize,
+    pub target: Point,
+}
+
+/*
+The energy an action costs. A creature with a speed of ACTION_COST acts once a turn.
+ */
+pub const ACTION_COST: i32 = 100;
+
+/*
+When a creature gets to act. Every turn it gains speed energy, and it acts whenever it has ACTION_COST to spend,
+so a creature twice as fast as the player acts twice for every move of theirs, and one half as fast every other turn.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Initiative {
+    pub speed: i32,
+    pub energy: i32,
+}
+
+impl Initiative {
+    /*
+    Creatures arrive with a turn's worth of energy, as if the turn had just begun.
+     */
+    pub fn new(speed: i32) -> Self {
+        Self { speed, energy: speed }
+    }
+
+    pub fn is_ready(self) -> bool {
+        self.energy >= ACTION_COST
+    }
+}
+
+/*
+What the player is made of. Every point of strength adds a hit point to each blow they land, every point of toughness
+takes one off each blow they take, though a blow always does at least one, and every point of agility
+gets them through slow ground a turn sooner.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Attributes {
+    pub strength: i32,
+    pub agility: i32,
+    pub toughness: i32,
+}
+
+/*
+The xp the player has earned so far, the level it has brought them to,
+and how many of those levels they still have to pick a perk for.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Experience {
+    pub level: i32,
+    pub xp: i32,
+    pub perks_to_pick: i32,
+}
+
+/*
+The xp earned for killing a monster. It comes from the monster's template.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct XpReward(pub i32);
+
+/*
+Message component: entity has earned xp, by landing the killing blow on something. The experience system deletes it.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EarnedXp {
+    pub entity: Entity,
+    pub xp: i32,
+}
+
+/*
+How well fed the player is. satiety goes down by one every turn, and eating puts it back up, to at most max.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hunger {
+    pub satiety: i32,
+    pub max: i32,
+}
+
+/*
+What hunger does to the player. Well fed, resting restores both health and mana; hungry, only mana;
+weak, neither; and starving, they lose a hit point every turn.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HungerState {
+    WellFed,
+    Hungry,
+    Weak,
+    Starving,
+}
+
+impl Hunger {
+    pub fn state(self) -> HungerState {
+        match self.satiety {
+            satiety if satiety > 400 => HungerState::WellFed,
+            satiety if satiety > 150 => HungerState::Hungry,
+            satiety if satiety > 0 => HungerState::Weak,
+            _ => HungerState::Starving,
+        }
+    }
+}
+
+impl HungerState {
+    pub fn name(self) -> &'static str {
+        match self {
+            HungerState::WellFed => "Well fed",
+            HungerState::Hungry => "Hungry",
+            HungerState::Weak => "Weak",
+            HungerState::Starving => "Starving",
+        }
+    }
+}
+
+/*
+Something to eat, worth nutrition points of satiety.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Food {
+    pub nutrition: i32,
+}
+
+/*
+Message component: eater is eating food. The eating system deletes it once the food is gone.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToEat {
+    pub eater: Entity,
+    pub food: Entity,
+}
+
+/*
+Message component: entity spends the turn resting. The resting system deletes it once the rest is over.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WantsToRest {
+    pub entity: Entity,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hunger_state_changes_just_past_each_threshold() {
+        let state = |satiety| Hunger { satiety, max: 500 }.state();
+        assert_eq!(state(500), HungerState::WellFed);
+        assert_eq!(state(401), HungerState::WellFed);
+        assert_eq!(state(400), HungerState::Hungry);
+        assert_eq!(state(151), HungerState::Hungry);
+        assert_eq!(state(150), HungerState::Weak);
+        assert_eq!(state(1), HungerState::Weak);
+        assert_eq!(state(0), HungerState::Starving);
+    }
+}
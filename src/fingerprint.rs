@@ -0,0 +1,229 @@
+use crate::prelude::*;
+
+/*
+64-bit FNV-1a. std's DefaultHasher may change from one Rust release to the next; this one never does,
+so a world hash recorded with one build of the game can be checked by another.
+ */
+pub struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0100_0000_01b3;
+
+    pub fn new() -> Self {
+        Self(Self::OFFSET_BASIS)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(Self::PRIME);
+        }
+    }
+
+    pub fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/*
+Feeds a value to the hasher, field by field, in an encoding that is spelled out here rather than left to
+Debug or derived Hash output, neither of which is promised to stay the same. Integers go in little-endian,
+strings with their length first, enums as a fixed tag followed by their fields.
+ */
+pub trait Fingerprint {
+    fn fingerprint(&self, hasher: &mut Fnv1a);
+}
+
+impl Fingerprint for i32 {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        hasher.write(&self.to_le_bytes());
+    }
+}
+
+impl Fingerprint for u32 {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        hasher.write(&self.to_le_bytes());
+    }
+}
+
+impl Fingerprint for bool {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        hasher.write(&[u8::from(*self)]);
+    }
+}
+
+impl Fingerprint for str {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        self.len().fingerprint(hasher);
+        hasher.write(self.as_bytes());
+    }
+}
+
+impl Fingerprint for usize {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        hasher.write(&(*self as u64).to_le_bytes());
+    }
+}
+
+impl<T: Fingerprint> Fingerprint for [T] {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        self.len().fingerprint(hasher);
+        for item in self {
+            item.fingerprint(hasher);
+        }
+    }
+}
+
+// Whether the component is there at all is part of the fingerprint.
+impl<T: Fingerprint> Fingerprint for Option<&T> {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        match self {
+            None => false.fingerprint(hasher),
+            Some(value) => {
+                true.fingerprint(hasher);
+                value.fingerprint(hasher);
+            }
+        }
+    }
+}
+
+impl Fingerprint for TileType {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        match self {
+            TileType::Wall => 0u32.fingerprint(hasher),
+            TileType::Floor => 1u32.fingerprint(hasher),
+            TileType::Door { open } => {
+                2u32.fingerprint(hasher);
+                open.fingerprint(hasher);
+            }
+            TileType::Exit => 3u32.fingerprint(hasher),
+            TileType::DeepWater => 4u32.fingerprint(hasher),
+            TileType::Lava => 5u32.fingerprint(hasher),
+            TileType::Chasm => 6u32.fingerprint(hasher),
+        }
+    }
+}
+
+impl Fingerprint for ThemeKind {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        let tag: u32 = match self {
+            ThemeKind::Dungeon => 0,
+            ThemeKind::Forest => 1,
+            ThemeKind::LavaCavern => 2,
+        };
+        tag.fingerprint(hasher);
+    }
+}
+
+impl Fingerprint for TurnState {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        let tag: u32 = match self {
+            TurnState::AwaitingInput => 0,
+            TurnState::PlayerTurn => 1,
+            TurnState::MonsterTurn => 2,
+            TurnState::NewTurn => 3,
+            TurnState::NextLevel => 4,
+            TurnState::GameOver => 5,
+        };
+        tag.fingerprint(hasher);
+    }
+}
+
+impl Fingerprint for TrapKind {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        let tag: u32 = match self {
+            TrapKind::Dart => 0,
+            TrapKind::Teleport => 1,
+            TrapKind::Alarm => 2,
+            TrapKind::Pit => 3,
+        };
+        tag.fingerprint(hasher);
+    }
+}
+
+impl Fingerprint for Alertness {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        match self {
+            Alertness::Asleep => 0u32.fingerprint(hasher),
+            Alertness::Wandering => 1u32.fingerprint(hasher),
+            Alertness::Chasing { target } => {
+                2u32.fingerprint(hasher);
+                target.fingerprint(hasher);
+            }
+            Alertness::Searching { target, turns } => {
+                3u32.fingerprint(hasher);
+                target.fingerprint(hasher);
+                turns.fingerprint(hasher);
+            }
+        }
+    }
+}
+
+impl Fingerprint for Name {
+    fn fingerprint(&self, hasher: &mut Fnv1a) {
+        self.0.fingerprint(hasher);
+    }
+}
+
+/*
+Structs are fingerprinted one field after the other, in the order listed.
+ */
+macro_rules! fingerprint_fields {
+    ($($component:ty { $($field:ident),* }),* $(,)?) => {
+        $(
+            impl Fingerprint for $component {
+                #[allow(unused_variables)]
+                fn fingerprint(&self, hasher: &mut Fnv1a) {
+                    $(self.$field.fingerprint(hasher);)*
+                }
+            }
+        )*
+    };
+}
+
+fingerprint_fields! {
+    Point { x, y },
+    Player { map_level },
+    Health { current, max },
+    Busy { turns },
+    Trap { kind },
+    Hidden {},
+    Mana { current, max },
+    Poisoned { damage, turns },
+    Confused { turns },
+    Hasted { turns },
+    Slowed { turns },
+    Initiative { speed, energy },
+    Experience { level, xp, perks_to_pick },
+    Attributes { strength, agility, toughness },
+    Hunger { satiety, max },
+    Food { nutrition },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fnv1a_matches_the_published_test_vectors() {
+        let hash = |bytes: &[u8]| {
+            let mut hasher = Fnv1a::new();
+            hasher.write(bytes);
+            hasher.finish()
+        };
+        assert_eq!(hash(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(hash(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(hash(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn a_missing_component_differs_from_an_empty_one() {
+        let hash = |hidden: Option<&Hidden>| {
+            let mut hasher = Fnv1a::new();
+            hidden.fingerprint(&mut hasher);
+            hasher.finish()
+        };
+        assert_ne!(hash(None), hash(Some(&Hidden)));
+    }
+}
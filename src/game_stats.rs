@@ -0,0 +1,27 @@
+use std::collections::BTreeMap;
+
+/*
+A running tally of how the game is going for the player, for balance reports (see balance.rs).
+Damage is tallied by whatever dealt it: a monster by its template name, a trap by its name, or Lava.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct GameStats {
+    // Turns the player has taken, including the ones spent busy.
+    pub turns: u32,
+    pub damage_taken: BTreeMap<String, i32>,
+    pub items_used: BTreeMap<String, u32>,
+    // Whatever dealt the finishing blow, once the player has died.
+    pub killed_by: Option<String>,
+    last_hurt_by: Option<String>,
+}
+
+impl GameStats {
+    pub fn player_hurt(&mut self, source: &str, damage: i32) {
+        *self.damage_taken.entry(source.to_string()).or_default() += damage;
+        self.last_hurt_by = Some(source.to_string());
+    }
+
+    pub fn player_died(&mut self) {
+        self.killed_by = self.last_hurt_by.clone();
+    }
+}
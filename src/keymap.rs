@@ -0,0 +1,237 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+
+/*
+Everything the player can ask the adventurer to do.
+Systems only ever look at actions, never at raw keys, so rebinding a key is just a change to the Keymap.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    MoveNorth,
+    MoveSouth,
+    MoveEast,
+    MoveWest,
+    MoveNorthEast,
+    MoveNorthWest,
+    MoveSouthEast,
+    MoveSouthWest,
+    Wait,
+    Eat,
+    Descend,
+    Look,
+    Search,
+    Fire,
+    CycleTarget,
+    Cancel,
+    Cast,
+}
+
+impl Action {
+    /*
+    Movement actions translate into the step they take on the map; every other action has no delta.
+     */
+    pub fn delta(self) -> Option<Point> {
+        match self {
+            Action::MoveNorth => Some(Point::new(0, -1)),
+            Action::MoveSouth => Some(Point::new(0, 1)),
+            Action::MoveEast => Some(Point::new(1, 0)),
+            Action::MoveWest => Some(Point::new(-1, 0)),
+            Action::MoveNorthEast => Some(Point::new(1, -1)),
+            Action::MoveNorthWest => Some(Point::new(-1, -1)),
+            Action::MoveSouthEast => Some(Point::new(1, 1)),
+            Action::MoveSouthWest => Some(Point::new(-1, 1)),
+            _ => None,
+        }
+    }
+}
+
+/*
+The keymap file lists, for each action, the names of the keys bound to it, e.g. MoveNorth: ["Up", "K", "Numpad8"].
+Key names are the VirtualKeyCode variant names.
+ */
+pub struct Keymap {
+    bindings: HashMap<VirtualKeyCode, Action>,
+}
+
+impl Keymap {
+    /*
+    Reads a keymap file. Missing or broken files fall back to the default bindings,
+    so a typo in the config never stops the game from starting.
+     */
+    pub fn load(path: &str) -> Self {
+        match Self::from_file(path) {
+            Ok(keymap) => keymap,
+            Err(e) => {
+                eprintln!("Using default keybindings, could not load {path}: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    fn from_file(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let file: HashMap<Action, Vec<String>> = ron::from_str(&text).map_err(|e| e.to_string())?;
+        Self::from_names(file)
+    }
+
+    /*
+    Builds a keymap from the same action-to-key-names table the keymap file holds.
+     */
+    pub fn from_names(names: HashMap<Action, Vec<String>>) -> Result<Self, String> {
+        let mut keymap = Self { bindings: HashMap::new() };
+        for (action, keys) in names {
+            for name in keys {
+                let key = key_from_name(&name).ok_or(format!("unknown key {name}"))?;
+                keymap.bind(key, action);
+            }
+        }
+        Ok(keymap)
+    }
+
+    /*
+    The opposite of from_names(). Key names are sorted, so the same keymap always comes out the same.
+     */
+    pub fn to_names(&self) -> HashMap<Action, Vec<String>> {
+        let mut names: HashMap<Action, Vec<String>> = HashMap::new();
+        for (key, action) in &self.bindings {
+            names.entry(*action).or_default().push(format!("{key:?}"));
+        }
+        for keys in names.values_mut() {
+            keys.sort();
+        }
+        names
+    }
+
+    pub fn bind(&mut self, key: VirtualKeyCode, action: Action) {
+        self.bindings.insert(key, action);
+    }
+
+    pub fn action(&self, key: VirtualKeyCode) -> Option<Action> {
+        self.bindings.get(&key).copied()
+    }
+
+    /*
+    A key bound to the action. When several are, the one with the shortest name is picked, the first of those
+    in alphabetical order, so it is always the same one, and letter keys win over the rest.
+     */
+    pub fn key_for(&self, action: Action) -> Option<VirtualKeyCode> {
+        self.bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| *key)
+            .min_by_key(|key| {
+                let name = format!("{key:?}");
+                (name.len(), name)
+            })
+    }
+}
+
+/*
+The default keymap binds every action three ways: arrow keys, vi-keys (hjkl for the cardinal directions, yubn for the diagonals)
+and the numeric keypad, so the game is playable on keyboards without a numpad.
+ */
+impl Default for Keymap {
+    fn default() -> Self {
+        use VirtualKeyCode as Key;
+        let defaults = [
+            (Action::MoveNorth, [Key::Up, Key::K, Key::Numpad8]),
+            (Action::MoveSouth, [Key::Down, Key::J, Key::Numpad2]),
+            (Action::MoveEast, [Key::Right, Key::L, Key::Numpad6]),
+            (Action::MoveWest, [Key::Left, Key::H, Key::Numpad4]),
+            (Action::MoveNorthEast, [Key::PageUp, Key::U, Key::Numpad9]),
+            (Action::MoveNorthWest, [Key::Home, Key::Y, Key::Numpad7]),
+            (Action::MoveSouthEast, [Key::PageDown, Key::N, Key::Numpad3]),
+            (Action::MoveSouthWest, [Key::End, Key::B, Key::Numpad1]),
+            (Action::Wait, [Key::Space, Key::Period, Key::Numpad5]),
+            (Action::Eat, [Key::E, Key::Comma, Key::NumpadAdd]),
+            (Action::Descend, [Key::Return, Key::NumpadEnter, Key::Insert]),
+            (Action::Look, [Key::X, Key::Semicolon, Key::NumpadDivide]),
+            (Action::Search, [Key::S, Key::Slash, Key::Numpad0]),
+            (Action::Fire, [Key::F, Key::Apostrophe, Key::NumpadDecimal]),
+            (Action::CycleTarget, [Key::C, Key::Minus, Key::NumpadSubtract]),
+            (Action::Cancel, [Key::Escape, Key::Back, Key::Delete]),
+            (Action::Cast, [Key::Z, Key::M, Key::NumpadEquals]),
+        ];
+
+        let mut keymap = Self { bindings: HashMap::new() };
+        for (action, keys) in defaults {
+            for key in keys {
+                keymap.bind(key, action);
+            }
+        }
+        keymap
+    }
+}
+
+/*
+Builds key_from_name() from a list of VirtualKeyCode variants, matching each one against its own name.
+ */
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        pub fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+            match name {
+                $(stringify!($key) => Some(VirtualKeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+key_names!(
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    Insert, Home, Delete, End, PageDown, PageUp, Left, Up, Right, Down,
+    Back, Return, Space,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    NumpadAdd, NumpadDivide, NumpadDecimal, NumpadComma, NumpadEnter, NumpadEquals,
+    NumpadMultiply, NumpadSubtract,
+    Apostrophe, Asterisk, At, Backslash, Colon, Comma, Equals, Grave, LBracket, Minus,
+    Period, Plus, RBracket, Semicolon, Slash, Tab,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shipped_keymap_matches_the_defaults() {
+        let shipped = Keymap::from_file(concat!(env!("CARGO_MANIFEST_DIR"), "/resources/keymap.ron")).unwrap();
+        assert_eq!(shipped.bindings, Keymap::default().bindings);
+    }
+
+    #[test]
+    fn key_from_name_knows_the_variant_names() {
+        assert_eq!(key_from_name("Space"), Some(VirtualKeyCode::Space));
+        assert_eq!(key_from_name("Numpad8"), Some(VirtualKeyCode::Numpad8));
+        assert_eq!(key_from_name("space"), None);
+    }
+
+    #[test]
+    fn vi_keys_move_diagonally() {
+        let keymap = Keymap::default();
+        assert_eq!(keymap.action(VirtualKeyCode::U).and_then(Action::delta), Some(Point::new(1, -1)));
+        assert_eq!(keymap.action(VirtualKeyCode::B).and_then(Action::delta), Some(Point::new(-1, 1)));
+        assert_eq!(keymap.action(VirtualKeyCode::Space).and_then(Action::delta), None);
+    }
+
+    #[test]
+    fn key_names_round_trip() {
+        let keymap = Keymap::default();
+        let reloaded = Keymap::from_names(keymap.to_names()).unwrap();
+        assert_eq!(reloaded.bindings, keymap.bindings);
+        assert_eq!(reloaded.to_names(), keymap.to_names());
+    }
+
+    #[test]
+    fn unknown_key_names_are_refused() {
+        assert!(Keymap::from_names(HashMap::from([(Action::Wait, vec!["NoSuchKey".to_string()])])).is_err());
+    }
+
+    #[test]
+    fn key_for_prefers_the_shortest_name() {
+        assert_eq!(Keymap::default().key_for(Action::MoveNorth), Some(VirtualKeyCode::K));
+    }
+}
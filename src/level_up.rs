@@ -0,0 +1,53 @@
+// Hit points the player's maximum health grows by with every level.
+pub const HEALTH_PER_LEVEL: i32 = 5;
+
+/*
+The xp the player needs, in total, to get past level: 10 for level 2, 40 for level 3, 90 for level 4, and so on.
+ */
+pub fn xp_to_leave_level(level: i32) -> i32 {
+    10 * level * level
+}
+
+/*
+What the player can pick from the level-up menu, once for every level they go up.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Perk {
+    Strength,
+    Agility,
+    Toughness,
+    ArcanePower,
+}
+
+pub const PERKS: [Perk; 4] = [Perk::Strength, Perk::Agility, Perk::Toughness, Perk::ArcanePower];
+
+impl Perk {
+    pub fn describe(self) -> &'static str {
+        match self {
+            Perk::Strength => "Strength: every blow you land does 1 more damage",
+            Perk::Agility => "Agility: slow ground holds you up a turn less",
+            Perk::Toughness => "Toughness: every blow you take does 1 less damage",
+            Perk::ArcanePower => "Arcane power: 3 more mana",
+        }
+    }
+}
+
+/*
+The level-up menu opens by itself whenever the player has a perk to pick. selected is the perk it points at.
+ */
+#[derive(Default)]
+pub struct LevelUpMenu {
+    pub selected: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xp_needed_grows_with_the_square_of_the_level() {
+        assert_eq!(xp_to_leave_level(1), 10);
+        assert_eq!(xp_to_leave_level(2), 40);
+        assert_eq!(xp_to_leave_level(3), 90);
+    }
+}
@@ -0,0 +1,95 @@
+use crate::prelude::*;
+
+// Tiles lit any dimmer than this are too dark to make anything out.
+const MIN_LIGHT: f32 = 0.1;
+// How bright a barely lit tile is drawn, as a fraction of its full color.
+const MIN_BRIGHTNESS: f32 = 0.35;
+
+/*
+How much light reaches each tile of the map, and what color it is, stored by map index.
+The lighting system recalculates it from every LightSource whenever something may have moved, before fields of view
+are updated. Each channel runs from 0.0 for darkness to 1.0 for full light; the brightest channel is the tile's intensity.
+Line of sight alone isn't enough to see a tile: it also has to be lit (see is_lit()).
+ */
+#[derive(Default)]
+pub struct Lighting {
+    light: Vec<RGB>,
+}
+
+impl Lighting {
+    /*
+    Darkens every tile down to the level's ambient light, resizing to fit the map if the level changed size.
+     */
+    pub fn clear(&mut self, map: &Map) {
+        let ambient = map.theme.theme().ambient_light();
+        self.light = vec![RGB::from_f32(ambient, ambient, ambient); map.tiles.len()];
+    }
+
+    /*
+    Adds a light's color to a tile, scaled by how strongly it reaches it.
+     */
+    pub fn add_light(&mut self, idx: usize, color: RGB, strength: f32) {
+        let light = &mut self.light[idx];
+        light.r = (light.r + color.r * strength).min(1.0);
+        light.g = (light.g + color.g * strength).min(1.0);
+        light.b = (light.b + color.b * strength).min(1.0);
+    }
+
+    pub fn intensity(&self, map: &Map, pos: Point) -> f32 {
+        map.try_idx(pos)
+            .and_then(|idx| self.light.get(idx))
+            .map_or(0.0, |light| light.r.max(light.g).max(light.b))
+    }
+
+    /*
+    Whether there is enough light on a tile to see what's there.
+     */
+    pub fn is_lit(&self, map: &Map, pos: Point) -> bool {
+        self.intensity(map, pos) >= MIN_LIGHT
+    }
+
+    /*
+    The color something on the tile is drawn in: darker the less light reaches it, and tinted by the light's color.
+     */
+    pub fn tint(&self, map: &Map, pos: Point, color: RGBA) -> RGBA {
+        let Some(light) = map.try_idx(pos).and_then(|idx| self.light.get(idx)) else {
+            return color;
+        };
+        let scale = |channel: f32| MIN_BRIGHTNESS + (1.0 - MIN_BRIGHTNESS) * channel;
+        RGBA::from_f32(color.r * scale(light.r), color.g * scale(light.g), color.b * scale(light.b), color.a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_dungeon_is_dark_until_a_light_reaches_it() {
+        let map = Map::new(3, 3);
+        let mut lighting = Lighting::default();
+        lighting.clear(&map);
+        assert!(!lighting.is_lit(&map, Point::new(1, 1)));
+
+        lighting.add_light(map.map_idx(1, 1), RGB::named(WHITE), 0.5);
+        lighting.add_light(map.map_idx(1, 1), RGB::named(WHITE), 0.8);
+        assert!(lighting.is_lit(&map, Point::new(1, 1)));
+        assert!((lighting.intensity(&map, Point::new(1, 1)) - 1.0).abs() < f32::EPSILON);
+        assert!(!lighting.is_lit(&map, Point::new(5, 5)));
+    }
+
+    #[test]
+    fn dark_tiles_are_drawn_dimmer_and_lit_ones_take_the_light_color() {
+        let map = Map::new(3, 3);
+        let mut lighting = Lighting::default();
+        lighting.clear(&map);
+        lighting.add_light(map.map_idx(0, 0), RGB::named(RED), 1.0);
+
+        let white = RGBA::from_f32(1.0, 1.0, 1.0, 1.0);
+        let dark = lighting.tint(&map, Point::new(1, 1), white);
+        assert!((dark.g - MIN_BRIGHTNESS).abs() < f32::EPSILON);
+        let red = lighting.tint(&map, Point::new(0, 0), white);
+        assert!((red.r - 1.0).abs() < f32::EPSILON);
+        assert!((red.g - MIN_BRIGHTNESS).abs() < f32::EPSILON);
+    }
+}
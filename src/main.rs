@@ -5,10 +5,30 @@
 // START: prelude
 mod map;
 mod map_builder;
+mod themes;
 mod camera;
 mod components;
 mod spawner;
+mod keymap;
+mod settings;
+mod turn_state;
+mod spatial_index;
+mod pathing;
+mod lighting;
+mod spells;
+mod level_up;
+mod noise;
+mod targeting;
+mod snapshot;
+mod map_export;
+mod fingerprint;
+mod replay;
+mod bot;
+mod soak;
+mod game_stats;
+mod balance;
 mod systems;
+mod terminal;
 
 mod prelude {
     pub use bracket_lib::prelude::*;
@@ -27,14 +47,35 @@ mod prelude {
     pub const SCREEN_HEIGHT: i32 = 50;
     pub const DISPLAY_WIDTH: i32 = SCREEN_WIDTH /2;
     pub const DISPLAY_HEIGHT: i32 = SCREEN_HEIGHT /2;
+    // The size of a regular dungeon level. Maps can be any size; the camera shows a DISPLAY_WIDTH x DISPLAY_HEIGHT window of them.
+    pub const MAP_WIDTH: i32 = 80;
+    pub const MAP_HEIGHT: i32 = 50;
 
     pub use crate::map::*;
     pub use crate::map_builder::*;
+    pub use crate::themes::*;
     pub use crate::camera::*;
+    pub use crate::keymap::*;
+    pub use crate::settings::*;
+    pub use crate::turn_state::*;
+    pub use crate::spatial_index::*;
+    pub use crate::pathing::*;
+    pub use crate::lighting::*;
+    pub use crate::targeting::*;
+    pub use crate::spells::*;
+    pub use crate::level_up::*;
+    pub use crate::noise::*;
+    pub use crate::snapshot::*;
+    pub use crate::map_export::*;
+    pub use crate::fingerprint::*;
+    pub use crate::replay::*;
+    pub use crate::bot::*;
+    pub use crate::soak::*;
+    pub use crate::game_stats::*;
+    pub use crate::balance::*;
 
 }
 
-use std::task::Poll::Ready;
 use crate::prelude::*;
 // END: prelude
 
@@ -44,42 +85,197 @@ use crate::prelude::*;
 struct State{
     ecs: World,
     resources: Resources,
-    systems: Schedule,
-
+    input_systems: Schedule,
+    player_systems: Schedule,
+    monster_systems: Schedule,
+    new_turn_systems: Schedule,
+    // Games played in a window or terminal are recorded as they go; games played without one aren't.
+    recording: Option<Recording>,
 }
 
 impl State {
+    /*
+    Every game gets a seed of its own, so it can be recorded and played back.
+     */
     fn new() -> Self {
+        let seed = RandomNumberGenerator::new().next_u64();
+        let settings = Settings::load("resources/settings.ron");
+        let keymap = Keymap::load("resources/keymap.ron");
+        let spellbook = Spellbook::load("resources/spells.ron");
+        let recording = Recording::new(seed, settings, &keymap, &spellbook);
+        let mut state = Self::configured(seed, settings, keymap, spellbook);
+        state.recording = Some(recording);
+        state
+    }
+
+    /*
+    A game that plays out the same way every time, given the same keys.
+     */
+    fn seeded(seed: u64) -> Self {
+        Self::configured(
+            seed,
+            Settings::load("resources/settings.ron"),
+            Keymap::load("resources/keymap.ron"),
+            Spellbook::load("resources/spells.ron"),
+        )
+    }
+
+    fn configured(seed: u64, settings: Settings, keymap: Keymap, spellbook: Spellbook) -> Self {
         let mut ecs = World::default();
         let mut resources = Resources::default();
-        let mut rng = RandomNumberGenerator::new();
+        let mut rng = RandomNumberGenerator::seeded(seed);
         //The map builder is set up in the same way we did before, but rather than storing it in State,
         // it’s injected into the world’s resources with insert().
-        let map_builder = MapBuilder::new(&mut rng);
+        let map_builder = MapBuilder::new(&mut rng, MAP_WIDTH, MAP_HEIGHT);
         spawn_player(&mut ecs, map_builder.player_start);
+        spawn_level(&mut ecs, &mut rng, &map_builder);
 
-        // Spawn one Monster per room, except in the first room with the player, where none will spawn.
-        // The Rect structure we used to place rooms includes a center() function.
-        // We can combine this with a bit of iterator magic to efficiently place a random monster in each room
-        map_builder.rooms
-            .iter()
-            .skip(1)
-            // This transforms each entry from a room to the result of center() (a Point) using map().
-            // Mapping an iterator passes each entry into a closure, returning a different type of result.
-            // We can use map() to transform one type of iterator into another.
-            // After this call, we iterate a list of Point data representing the center of each room.
-            .map(|r| r.center())
-            // This calls for_each to run a closure on each location.
-            // The closure receives the point as pos and calls our spawn_monster() function with the location.
-            .for_each(|pos| spawn_monster(&mut ecs, &mut rng, pos));
-
-
+        resources.insert(Camera::new(map_builder.player_start, &map_builder.map, settings.camera_dead_zone));
         resources.insert(map_builder.map);
-        resources.insert(Camera::new(map_builder.player_start));
+        resources.insert(keymap);
+        resources.insert(settings);
+        resources.insert(TurnState::AwaitingInput);
+        resources.insert(SpatialIndex::default());
+        resources.insert(Lighting::default());
+        resources.insert(Targeting::default());
+        resources.insert(spellbook);
+        resources.insert(LevelUpMenu::default());
+        resources.insert(Noises::default());
+        resources.insert(GameStats::default());
+        resources.insert(Point::zero());
+        //The generator moves into the resources too, so systems like monster_ai share the same random stream.
+        resources.insert(rng);
         Self {
             ecs,
             resources,
-            systems: build_scheduler()
+            input_systems: build_input_scheduler(),
+            player_systems: build_player_scheduler(),
+            monster_systems: build_monster_scheduler(),
+            new_turn_systems: build_new_turn_scheduler(),
+            recording: None,
+        }
+    }
+
+    /*
+    Going down the stairs (or a chasm) throws away everything except the player and builds a fresh level around them.
+     */
+    fn advance_level(&mut self) {
+        let player_entity = *<Entity>::query()
+            .filter(component::<Player>())
+            .iter(&self.ecs)
+            .next()
+            .unwrap();
+
+        let mut cb = CommandBuffer::new(&self.ecs);
+        <Entity>::query()
+            .iter(&self.ecs)
+            .filter(|entity| **entity != player_entity)
+            .for_each(|entity| cb.remove(*entity));
+        cb.flush(&mut self.ecs);
+
+        let map_builder = {
+            let mut rng = self.resources.get_mut::<RandomNumberGenerator>().unwrap();
+            let map_builder = MapBuilder::new(&mut rng, MAP_WIDTH, MAP_HEIGHT);
+            spawn_level(&mut self.ecs, &mut rng, &map_builder);
+            map_builder
+        };
+
+        if let Some(mut player) = self.ecs.entry(player_entity) {
+            player.add_component(map_builder.player_start);
+            let fov = player.get_component::<FieldOfView>().unwrap().clone_dirty();
+            player.add_component(fov);
+            player.get_component_mut::<Player>().unwrap().map_level += 1;
+            // The player starts the level at the start of a turn, like the monsters.
+            let speed = player.get_component::<Initiative>().unwrap().speed;
+            player.add_component(Initiative::new(speed));
+        }
+
+        let dead_zone = self.resources.get::<Settings>().unwrap().camera_dead_zone;
+        self.resources.insert(Camera::new(map_builder.player_start, &map_builder.map, dead_zone));
+        self.resources.insert(map_builder.map);
+        // Nothing on the new level heard what went on upstairs.
+        self.resources.insert(Noises::default());
+        self.resources.insert(TurnState::AwaitingInput);
+    }
+
+    /*
+    Runs one frame of the game: whichever schedule matches whose turn it is, or the move to the next level.
+    Nothing here needs a window, so the game can also be played without one, as long as someone
+    clears out the draw commands the render systems leave behind.
+     */
+    fn step(&mut self, key: Option<VirtualKeyCode>) {
+        //The keymap turns the key into the Action it is bound to, which goes into the resources.
+        //This makes what the player asked for available to any system that requests it; unbound keys are ignored.
+        let action = key.and_then(|key| self.resources.get::<Keymap>().unwrap().action(key));
+        self.resources.insert(action);
+        //Which schedule runs depends on whose turn it is.
+        let current_state = *self.resources.get::<TurnState>().unwrap();
+        match current_state {
+            TurnState::AwaitingInput => self.input_systems.execute(&mut self.ecs, &mut self.resources),
+            TurnState::PlayerTurn => self.player_systems.execute(&mut self.ecs, &mut self.resources),
+            TurnState::MonsterTurn => self.monster_systems.execute(&mut self.ecs, &mut self.resources),
+            TurnState::NewTurn => self.new_turn_systems.execute(&mut self.ecs, &mut self.resources),
+            TurnState::NextLevel => self.advance_level(),
+            TurnState::GameOver => {}
+        }
+    }
+
+    fn waiting_for_player(&self) -> bool {
+        matches!(*self.resources.get::<TurnState>().unwrap(), TurnState::AwaitingInput | TurnState::GameOver)
+    }
+
+    /*
+    Without a window: the frame the window would draw before any key is pressed,
+    which works out what the player can see. Start every headless game with it.
+     */
+    fn first_frame(&mut self) {
+        self.step(None);
+        clear_command_buffer().expect("Render Error");
+    }
+
+    /*
+    Without a window: runs frames until the game waits for the player again, throwing away what they draw.
+     */
+    fn play_until_input(&mut self) {
+        while !self.waiting_for_player() {
+            self.step(None);
+            clear_command_buffer().expect("Render Error");
+        }
+    }
+
+    /*
+    Without a window: lets the game catch up to the player, then hands it one frame's input.
+    This is the frame a replay records, so pressing the recorded keys one after the other plays the same game.
+     */
+    fn press_key(&mut self, key: Option<VirtualKeyCode>) {
+        self.play_until_input();
+        self.step(key);
+        clear_command_buffer().expect("Render Error");
+    }
+
+    fn snapshot(&self) -> String {
+        snapshot(&self.ecs, &self.resources.get::<Map>().unwrap(), &self.resources.get::<Lighting>().unwrap())
+    }
+
+    /*
+    The death screen names the adventurer and whatever dealt the finishing blow. It stays up until the player asks for a new game.
+     */
+    fn game_over(&mut self, ctx: &mut BTerm) {
+        let mut player = <&Name>::query().filter(component::<Player>());
+        let name = player.iter(&self.ecs).next().map_or_else(|| "The adventurer".to_string(), |name| name.0.clone());
+        let killed_by = self.resources.get::<GameStats>().and_then(|stats| stats.killed_by.clone());
+        let epitaph = match killed_by {
+            Some(killer) => format!("{name} was slain by {killer}."),
+            None => format!("{name} has fallen in the depths of the dungeon."),
+        };
+
+        ctx.set_active_console(2);
+        ctx.print_color_centered(2, RED, BLACK, "Your quest has ended.");
+        ctx.print_color_centered(4, WHITE, BLACK, &epitaph);
+        ctx.print_color_centered(8, GREEN, BLACK, "Press Return to play again.");
+
+        if ctx.key == Some(VirtualKeyCode::Return) {
+            *self = State::new();
         }
     }
 }
@@ -90,16 +286,96 @@ impl GameState for State {
         ctx.cls();
         ctx.set_active_console(1);
         ctx.cls();
-        //We added ctx.key (which holds the keyboard state) as a resource in our tick() function.
-        //This makes the current keyboard state available to any system that requests it.
-        self.resources.insert(ctx.key);
-        self.systems.execute(&mut self.ecs, &mut self.resources);
+        ctx.set_active_console(2);
+        ctx.cls();
+        //The mouse position is read relative to the map layer, so tooltips can line it up with the tiles underneath.
+        ctx.set_active_console(0);
+        self.resources.insert(Point::from_tuple(ctx.mouse_pos()));
+        let waiting = self.waiting_for_player();
+        if let Some(recording) = self.recording.as_mut().filter(|_| waiting) {
+            recording.save_if_unsaved(|| world_hash(&self.ecs, &self.resources));
+        }
+
+        let turn_state = *self.resources.get::<TurnState>().unwrap();
+        if turn_state == TurnState::GameOver {
+            self.game_over(ctx);
+        } else {
+            self.step(ctx.key);
+            // Frames waiting for the player only matter to a replay if a key was pressed or the turn moved on anyway.
+            let moved_on = *self.resources.get::<TurnState>().unwrap() != TurnState::AwaitingInput;
+            if turn_state == TurnState::AwaitingInput && (ctx.key.is_some() || moved_on) {
+                if let Some(recording) = &mut self.recording {
+                    recording.record(ctx.key);
+                }
+            }
+        }
         render_draw_buffer(ctx).expect("Render Error");
     }
 
 }
 
+/*
+Plays a seeded game without a window and prints what the map looks like afterwards, e.g.
+    cargo run -- --snapshot 42 "Right Right Down Space"
+Keys are named as in keymap.ron.
+ */
+fn print_snapshot(seed: Option<&String>, keys: Option<&String>) -> BError {
+    let seed = seed.ok_or("--snapshot needs a seed")?.parse::<u64>()?;
+    let mut state = State::seeded(seed);
+    state.first_frame();
+    for name in keys.map(String::as_str).unwrap_or_default().split_whitespace() {
+        let key = key_from_name(name).ok_or(format!("unknown key {name}"))?;
+        state.press_key(Some(key));
+    }
+    state.press_key(None);
+    state.play_until_input();
+    print!("{}", state.snapshot());
+    Ok(())
+}
+
+/*
+Plays a recorded game back without a window, prints how it ended, and checks it ended the same way as when it was recorded:
+    cargo run -- --replay replays/1234.ron
+ */
+fn play_replay(path: Option<&String>) -> BError {
+    let replay = Replay::load(path.ok_or("--replay needs a file")?)?;
+    let keymap = Keymap::from_names(replay.keymap.clone())?;
+    let mut state = State::configured(replay.seed, replay.settings, keymap, replay.spellbook.clone());
+    state.first_frame();
+    replay.keys().for_each(|key| state.press_key(key));
+    state.play_until_input();
+    print!("{}", state.snapshot());
+
+    let hash = world_hash(&state.ecs, &state.resources);
+    if hash != replay.world_hash {
+        return Err(format!("Replay desynced: the world hash is {hash}, but {} was recorded", replay.world_hash).into());
+    }
+    println!("Replay of {} inputs matches the recorded game (world hash {hash})", replay.inputs.len());
+    Ok(())
+}
+
 fn main() -> BError {
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(at) = args.iter().position(|arg| arg == "--snapshot") {
+        return print_snapshot(args.get(at + 1), args.get(at + 2));
+    }
+    if let Some(at) = args.iter().position(|arg| arg == "--soak") {
+        return soak_from_args(args.get(at + 1), args.get(at + 2));
+    }
+    if let Some(at) = args.iter().position(|arg| arg == "--balance") {
+        return balance_from_args(&args[at + 1 ..]);
+    }
+    if let Some(at) = args.iter().position(|arg| arg == "--replay") {
+        return play_replay(args.get(at + 1));
+    }
+    if let Some(at) = args.iter().position(|arg| arg == "--export-maps") {
+        return export_maps_from_args(&args[at + 1 ..]);
+    }
+    // Playing in a text terminal instead of a window, e.g. over SSH.
+    if args.iter().any(|arg| arg == "--terminal") {
+        return terminal::run(State::new());
+    }
+
     let context = BTermBuilder::new()
         .with_title("Dungeon Crawler")
         .with_fps_cap(30.0)
@@ -111,13 +387,60 @@ fn main() -> BError {
         .with_resource_path("resources/")
         //This is the name of the font file to load and the character dimensions. These are usually the same as tile dimensions but can be different in some advanced forms of rendering.
         .with_font("dungeonfont.png", 32, 32)
+        //A smaller text font for the HUD. bracket-lib ships terminal8x8.png built in.
+        .with_font("terminal8x8.png", 8, 8)
         //This adds a console using the dimensions already specified and the named tile graphics file.
         .with_simple_console(DISPLAY_WIDTH, DISPLAY_HEIGHT, "dungeonfont.png")
         // This adds a second console, with no background so that transparency shows through it.
         .with_simple_console_no_bg(DISPLAY_WIDTH, DISPLAY_HEIGHT, "dungeonfont.png")
+        // A third layer for the HUD and tooltips, four times the resolution of the map layers.
+        .with_simple_console_no_bg(SCREEN_WIDTH*2, SCREEN_HEIGHT*2, "terminal8x8.png")
         .build()?;
 
     main_loop(context, State::new())
 
 
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SEED: u64 = 42;
+    const KEYS: [&str; 8] = ["Right", "Right", "Down", "Down", "Left", "Space", "Up", "S"];
+
+    fn play(seed: u64, keys: &[&str]) -> State {
+        let mut state = State::seeded(seed);
+        state.first_frame();
+        for name in keys {
+            state.press_key(key_from_name(name));
+        }
+        state.play_until_input();
+        state
+    }
+
+    /*
+    A few turns of a seeded game must draw exactly the screen stored in testdata/. When a change is meant to alter it,
+    run the test with UPDATE_SNAPSHOTS=1 to write the new screen, and check the difference before committing it.
+     */
+    #[test]
+    fn seeded_game_matches_golden_snapshot() {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join(format!("testdata/seed-{SEED}.txt"));
+        let snapshot = play(SEED, &KEYS).snapshot();
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::write(&path, &snapshot).unwrap();
+        }
+        let golden = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(snapshot, golden, "the seed {SEED} game no longer matches {}", path.display());
+    }
+
+    #[test]
+    fn same_seed_and_keys_play_the_same_game() {
+        let first = play(SEED, &KEYS);
+        let second = play(SEED, &KEYS);
+        assert_eq!(
+            world_hash(&first.ecs, &first.resources),
+            world_hash(&second.ecs, &second.resources)
+        );
+    }
+}
@@ -1,6 +1,5 @@
 // START: header
 use crate::prelude::*;
-const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT ) as usize;
 
 // END: header
 
@@ -8,10 +7,53 @@ const NUM_TILES: usize = (SCREEN_WIDTH * SCREEN_HEIGHT ) as usize;
     so the wildcard in our prelude allows any part of the program
     that uses the prelude to use the TileType enumeration.
     */
-#[derive(Copy, Clone, PartialEq)]
+#[derive(Copy, Clone, PartialEq, Hash)]
 pub enum TileType {
     Wall,
     Floor,
+    // Closed doors block both movement and sight until something opens them.
+    Door { open: bool },
+    // Stairs down to the next level.
+    Exit,
+    DeepWater,
+    Lava,
+    Chasm,
+}
+
+// Hit points lost by anything stepping into lava.
+const LAVA_DAMAGE: i32 = 5;
+
+/*
+The rules for entering each kind of tile.
+Movement and monster AI only ever ask these questions, so a new tile type only needs a line in each.
+ */
+impl TileType {
+    /*
+    How many turns it takes to step onto the tile, or None if it can't be entered at all.
+    Deep water stops anything that can't swim, and slows down anything that can.
+     */
+    pub fn move_cost(self, can_swim: bool) -> Option<i32> {
+        match self {
+            TileType::Floor | TileType::Door { open: true } | TileType::Exit
+                | TileType::Lava | TileType::Chasm => Some(1),
+            TileType::DeepWater if can_swim => Some(2),
+            TileType::Wall | TileType::Door { open: false } | TileType::DeepWater => None,
+        }
+    }
+
+    /*
+    Hit points lost by anything entering the tile.
+     */
+    pub fn entry_damage(self) -> i32 {
+        if self == TileType::Lava { LAVA_DAMAGE } else { 0 }
+    }
+
+    /*
+    Tiles that can be walked onto but are better avoided: they slow, hurt or make you fall.
+     */
+    pub fn is_hazard(self) -> bool {
+        matches!(self, TileType::DeepWater | TileType::Lava | TileType::Chasm)
+    }
 }
 
 /*
@@ -19,42 +61,88 @@ The map is no longer part of our game state.
 Instead, it’s now a resource—a shared piece of data that’s available to all systems that need it.
 Map creation remains the same, but instead of storing it in the State,
 it’s inserted into Legion’s resource list, which we also have to initialize.
+ The map carries its level's theme with it, so whatever draws the map knows how the level should look.
  */
 pub struct Map {
+    // The map has its own size, independent of how much of it fits on the screen.
+    pub width: i32,
+    pub height: i32,
     pub tiles: Vec<TileType>,
+    // Tiles the player has seen at some point; only these are drawn.
+    pub revealed_tiles: Vec<bool>,
+    // How the level is drawn; the map builder picks one for each level.
+    pub theme: ThemeKind,
 }
 
-pub fn map_idx(x: i32, y: i32)-> usize {
-    ((y * SCREEN_WIDTH) + x ) as usize
-}
 impl Map {
 
     /*
-    The constructor uses an extended form of the vec! macro to create a NUM_TILES number of entries,
+    The constructor uses an extended form of the vec! macro to create width * height entries,
     each set to TileType::Floor, creating a map that consists entirely of floors.
      */
-    pub fn new() -> Self {
+    #[allow(clippy::cast_sign_loss)]
+    pub fn new(width: i32, height: i32) -> Self {
+        let num_tiles = (width * height) as usize;
         Self {
-            tiles: vec![TileType::Floor; NUM_TILES]
+            width,
+            height,
+            tiles: vec![TileType::Floor; num_tiles],
+            revealed_tiles: vec![false; num_tiles],
+            theme: ThemeKind::Dungeon,
         }
     }
 
+    /*
+    Tiles are stored row by row, so the index of x/y is y rows of width tiles, plus x.
+    The coordinates must be inside the map; use try_idx() when they might not be.
+     */
+    #[allow(clippy::cast_sign_loss)]
+    pub fn map_idx(&self, x: i32, y: i32)-> usize {
+        ((y * self.width) + x ) as usize
+    }
+
     /*
     This function checks that the location specified in point is greater
-    than 0 on both the x and y axes and that it’s less than the screen height and width.
+    than 0 on both the x and y axes and that it’s less than the map height and width.
      */
     pub fn in_bounds(&self, point : Point ) -> bool {
-        point.x >= 0 && point.x < SCREEN_WIDTH && point.y >= 0 && point.y < SCREEN_HEIGHT
+        point.x >= 0 && point.x < self.width && point.y >= 0 && point.y < self.height
+    }
+
+    /*
+        We need a second function to determine if the player can enter a tile, and how long that takes.
+        Players can walk on floors, but not through walls. This function calls the in_bounds
+        function we just wrote (through tile()), to ensure that the move is valid dimensionally,
+        and then asks the TileType. Swimmers can also cross deep water, slowly.
+        None means the tile can't be entered at all.
+     */
+    pub fn move_cost(&self, point: Point, can_swim: bool) -> Option<i32> {
+        self.tile(point).and_then(|tile| tile.move_cost(can_swim))
+    }
+
+    pub fn tile(&self, point: Point) -> Option<TileType> {
+        self.try_idx(point).map(|idx| self.tiles[idx])
+    }
+
+    pub fn is_closed_door(&self, point: Point) -> bool {
+        self.try_idx(point)
+            .is_some_and(|idx| self.tiles[idx] == TileType::Door { open: false })
+    }
+
+    pub fn open_door(&mut self, point: Point) {
+        if let Some(idx) = self.try_idx(point) {
+            self.tiles[idx] = TileType::Door { open: true };
+        }
     }
 
     /*
-        We need a second function to determine if the player can enter a tile.
-        Players can walk on floors, but not through walls. This function should call the in_bounds
-        function we just wrote, to ensure that the move is valid both dimensionally and for the TileType.
-        If both are true, the adventurer may enter the tile.
+    A diagonal step cuts a corner when the two tiles it squeezes between are both solid.
      */
-    pub fn can_enter_tile(&self, point: Point) -> bool {
-        self.in_bounds(point) && self.tiles[map_idx(point.x, point.y)] == TileType::Floor
+    pub fn cuts_corner(&self, from: Point, destination: Point) -> bool {
+        let is_solid = |point| self.try_idx(point).is_none_or(|idx| self.is_opaque(idx));
+        from.x != destination.x && from.y != destination.y
+            && is_solid(Point::new(destination.x, from.y))
+            && is_solid(Point::new(from.x, destination.y))
     }
 
     /*
@@ -62,10 +150,31 @@ impl Map {
     failing if the requested coordinates fall outside of the map boundaries.
      */
     pub fn try_idx(&self, point: Point) -> Option<usize> {
-        if !self.in_bounds(point){
-            None
+        if self.in_bounds(point){
+            Some(self.map_idx(point.x, point.y))
         } else {
-            Some(map_idx(point.x, point.y))
+            None
         }
     }
-}
\ No newline at end of file
+}
+
+/*
+bracket-lib's field of view and path finding work on any map that implements these two traits.
+Algorithm2D tells it how big the map is, BaseMap which tiles light cannot pass through.
+Paths are found on a PathingMap instead, which knows who is walking them.
+ */
+impl Algorithm2D for Map {
+    fn dimensions(&self) -> Point {
+        Point::new(self.width, self.height)
+    }
+
+    fn in_bounds(&self, point: Point) -> bool {
+        self.in_bounds(point)
+    }
+}
+
+impl BaseMap for Map {
+    fn is_opaque(&self, idx: usize) -> bool {
+        matches!(self.tiles[idx], TileType::Wall | TileType::Door { open: false })
+    }
+}
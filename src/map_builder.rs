@@ -1,10 +1,15 @@
 use crate::prelude::*;
-const NUM_ROOMS: usize = 20;
+use std::collections::VecDeque;
+// One room for every this many tiles of map, which gives an 80x50 map twenty rooms.
+const TILES_PER_ROOM: i32 = 200;
+// Rooms that keep overlapping are given up on after this many tries, so small maps can't loop forever.
+const MAX_ROOM_ATTEMPTS: usize = 1000;
 
 pub struct MapBuilder {
     pub map: Map,
     pub rooms: Vec<Rect>,
     pub player_start : Point,
+    pub exit: Point,
 }
 
 impl MapBuilder {
@@ -21,21 +26,29 @@ impl MapBuilder {
 
 
     fn build_random_rooms(&mut self, rng: &mut RandomNumberGenerator) {
-        // Loop that continually generates rooms until NUM_ROOMS rooms exist.
-        while self.rooms.len() < NUM_ROOMS {
+        let num_rooms = usize::try_from(self.map.width * self.map.height / TILES_PER_ROOM)
+            .unwrap_or(0)
+            .max(1);
+        // Rooms are at most 10 tiles across, less on maps too small for that.
+        let max_size = i32::min(10, i32::min(self.map.width, self.map.height) - 2);
+
+        // Loop that continually generates rooms until num_rooms rooms exist.
+        let mut attempts = 0;
+        while self.rooms.len() < num_rooms && attempts < MAX_ROOM_ATTEMPTS {
+            attempts += 1;
             //This generates a randomly positioned room, with random sizes.
             let room = Rect::with_size(
-                rng.range(1, SCREEN_WIDTH -10),
-                rng.range(1, SCREEN_HEIGHT -10),
-                rng.range(2,10),
-                rng.range(2,10),
+                rng.range(1, i32::max(2, self.map.width - max_size)),
+                rng.range(1, i32::max(2, self.map.height - max_size)),
+                rng.range(2, max_size),
+                rng.range(2, max_size),
             );
 
             let mut overlap = false;
             //The tests the new room against each previously placed room and flags it as overlapping if they intersect.
             // also  uses the Rect type that includes a function named for_each().
             // It runs the provided closure on every x/y coordinate inside the rectangle it represents.
-            for r in self.rooms.iter() {
+            for r in &self.rooms {
                 if r.intersect(&room) {
                     overlap = true;
                 }
@@ -45,15 +58,15 @@ impl MapBuilder {
             // that they’re within the map boundaries and sets their contents to floors.
             if !overlap {
                 room.for_each(|p| {
-                    if p.x > 0 && p.x < SCREEN_WIDTH && p.y > 0
-                        && p.y <SCREEN_HEIGHT
+                    if p.x > 0 && p.x < self.map.width && p.y > 0
+                        && p.y < self.map.height
                     {
-                        let idx = map_idx(p.x, p.y);
+                        let idx = self.map.map_idx(p.x, p.y);
                         self.map.tiles[idx] = TileType::Floor;
                     }
                 });
 
-                self.rooms.push(room)
+                self.rooms.push(room);
             }
         }
     }
@@ -73,7 +86,7 @@ impl MapBuilder {
         use std::cmp::{min, max};
         for y in min(y1,y2) ..= max(y1,y2) {
             if let Some(idx) = self.map.try_idx(Point::new(x, y)) {
-                self.map.tiles[idx as usize] = TileType::Floor;
+                self.map.tiles[idx] = TileType::Floor;
             }
         }
     }
@@ -82,7 +95,7 @@ impl MapBuilder {
         use std::cmp::{min, max};
         for x in min(x1,x2) ..= max(x1,x2) {
             if let Some(idx) = self.map.try_idx(Point::new(x, y)) {
-                self.map.tiles[idx as usize] = TileType::Floor;
+                self.map.tiles[idx] = TileType::Floor;
             }
         }
     }
@@ -90,20 +103,17 @@ impl MapBuilder {
 
     /*
     This function uses the last two functions to generate complete corridors between rooms
-     Vectors include a sort_by() to sort their contents.
-     It requires the closure of an inline function that calls the cmp() function on two elements of the vector’s contents.
-     The cmp() function returns an indicator if two elements are the same, or if one is greater than the other.
+     Vectors include a sort_by_key() to sort their contents.
+     It requires a closure that returns the key to sort each element of the vector by.
      Sorting the rooms by their center point before allocating corridors makes it more likely that corridors will connect adjacent rooms without snaking across the whole map.
-     The sort_by() function sends pairs of rooms to the closure.
-     The closure receives these as a and b. a.center().x finds the x coordinate of room A.
-      This is then compared with room B center using the cmp() function.
+     The sort_by_key() function sends each room to the closure, and r.center().x finds the x coordinate of its center.
       This reorders the rooms to be sorted by the x order of their central points.
       Doing this shortens corridors between the rooms.
       If we don’t sort our rooms, we may receive very long corridors that likely overlap with other rooms.
      */
     fn build_corridors(&mut self, rng: &mut RandomNumberGenerator) {
         let mut rooms = self.rooms.clone();
-        rooms.sort_by(|a,b| a.center().x.cmp(&b.center().x));
+        rooms.sort_by_key(|r| r.center().x);
 
         // The skip() function allows us to ignore some entries in the iterator. We’re ignoring the first one,
         for (i,room) in rooms.iter().enumerate().skip(1) {
@@ -118,18 +128,66 @@ impl MapBuilder {
                 self.apply_horizontal_tunnel(prev.x, new.x, new.y);
             }
         }
+
+        self.add_doors();
+    }
+
+    /*
+    A corridor enters a room through a gap in the ring of tiles just outside the room.
+    When that gap is a single tile wide, with wall on either side of it, it gets a closed door.
+    Wider gaps are where a corridor runs along the room or two rooms touch, and are left open.
+    Where two rooms' rings meet, only one of the two neighbouring doorways gets a door.
+     */
+    fn add_doors(&mut self) {
+        let mut doors = Vec::new();
+        for room in &self.rooms {
+            for x in room.x1 .. room.x2 {
+                doors.push((Point::new(x, room.y1 - 1), Point::new(1, 0)));
+                doors.push((Point::new(x, room.y2), Point::new(1, 0)));
+            }
+            for y in room.y1 .. room.y2 {
+                doors.push((Point::new(room.x1 - 1, y), Point::new(0, 1)));
+                doors.push((Point::new(room.x2, y), Point::new(0, 1)));
+            }
+        }
+
+        for (pos, along) in doors {
+            let is_doorway = self.map.try_idx(pos)
+                .is_some_and(|idx| self.map.tiles[idx] == TileType::Floor)
+                && self.is_wall(pos - along)
+                && self.is_wall(pos + along);
+            let next_to_door = [Point::new(1, 0), Point::new(-1, 0), Point::new(0, 1), Point::new(0, -1)]
+                .iter()
+                .any(|d| self.map.is_closed_door(pos + *d));
+            if is_doorway && !next_to_door {
+                let idx = self.map.map_idx(pos.x, pos.y);
+                self.map.tiles[idx] = TileType::Door { open: false };
+            }
+        }
+    }
+
+    fn is_wall(&self, pos: Point) -> bool {
+        self.map.try_idx(pos).is_none_or(|idx| self.map.tiles[idx] == TileType::Wall)
     }
 
 
 
     /*
     The constructor calls all the elements we just created.
+    The map can be any size from 5x5 upwards; the number of rooms grows with its area.
      */
-    pub fn new(rng: &mut RandomNumberGenerator) -> Self {
+    pub fn new(rng: &mut RandomNumberGenerator, width: i32, height: i32) -> Self {
+        assert!(width >= 5 && height >= 5, "Maps must be at least 5x5 tiles, not {width}x{height}");
         let mut mb = MapBuilder{
-            map: Map::new(),
+            map: Map::new(width, height),
             rooms: Vec::new(),
-            player_start: Point::zero()
+            player_start: Point::zero(),
+            exit: Point::zero(),
+        };
+        mb.map.theme = match rng.range(0, 3) {
+            0 => ThemeKind::Dungeon,
+            1 => ThemeKind::Forest,
+            _ => ThemeKind::LavaCavern,
         };
         mb.fill(TileType::Wall);
         mb.build_random_rooms(rng);
@@ -137,6 +195,208 @@ impl MapBuilder {
         //This sets player_start to the center of the first room in the rooms list.
         // This ensures that they start in a valid, walkable tile.
         mb.player_start = mb.rooms[0].center();
+        mb.place_exit();
+        mb.build_hazards(rng);
         mb
     }
-}
\ No newline at end of file
+
+    /*
+    The stairs down go on the walkable tile furthest from where the player starts.
+     */
+    fn place_exit(&mut self) {
+        let distances = self.safe_distances();
+        let (idx, _) = distances
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, d)| d.map(|d| (idx, d)))
+            .max_by_key(|(_, d)| *d)
+            .unwrap();
+        self.exit = self.map.index_to_point2d(idx);
+        self.map.tiles[idx] = TileType::Exit;
+    }
+
+    /*
+    Floods outward from the player's start over tiles that are safe to walk on,
+    returning how many steps it takes to reach each tile, or None for tiles that can't be reached.
+    Doors count as safe, because anything that walks can open them.
+     */
+    fn safe_distances(&self) -> Vec<Option<i32>> {
+        let mut distances = vec![None; self.map.tiles.len()];
+        let start = self.map.map_idx(self.player_start.x, self.player_start.y);
+        distances[start] = Some(0);
+
+        let mut open_list = VecDeque::from([self.player_start]);
+        while let Some(pos) = open_list.pop_front() {
+            let distance = distances[self.map.map_idx(pos.x, pos.y)].unwrap();
+            for delta in [Point::new(1, 0), Point::new(-1, 0), Point::new(0, 1), Point::new(0, -1)] {
+                let next = pos + delta;
+                if let Some(idx) = self.map.try_idx(next) {
+                    if self.is_safe(next) && distances[idx].is_none() {
+                        distances[idx] = Some(distance + 1);
+                        open_list.push_back(next);
+                    }
+                }
+            }
+        }
+        distances
+    }
+
+    fn is_safe(&self, pos: Point) -> bool {
+        matches!(self.map.tile(pos), Some(TileType::Floor | TileType::Door { .. } | TileType::Exit))
+    }
+
+    /*
+    Whether taking away the safe tile at pos could cut some safe tiles off from others. The tiles around it are walked
+    in a ring; as long as all of its safe neighbours (up, down, left and right) lie on one unbroken stretch of safe
+    tiles, they can still reach each other around pos, and nothing further away can be cut off either.
+     */
+    fn could_cut_path(&self, pos: Point) -> bool {
+        const RING: [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+        let safe: Vec<bool> = RING.iter().map(|(dx, dy)| self.is_safe(pos + Point::new(*dx, *dy))).collect();
+        // A stretch starts at a safe tile after an unsafe one; the neighbours are the even entries of the ring.
+        let mut stretches_with_neighbours = 0;
+        for start in 0 .. RING.len() {
+            let previous = (start + RING.len() - 1) % RING.len();
+            if !safe[start] || safe[previous] {
+                continue;
+            }
+            let has_neighbour = (start .. start + RING.len()).take_while(|i| safe[i % RING.len()]).any(|i| i % 2 == 0);
+            if has_neighbour {
+                stretches_with_neighbours += 1;
+            }
+        }
+        // A ring that is safe all the way round has no start, and can't be cut.
+        stretches_with_neighbours > 1
+    }
+
+    /*
+    Hazards come as pools inside rooms and rivers winding across the map, which only show where they cross a room or corridor.
+    Each pool is all of one kind, picked from the level's theme. Rivers are water or lava, whichever the theme favors.
+    The first room, where the player starts, is always left dry.
+     */
+    fn build_hazards(&mut self, rng: &mut RandomNumberGenerator) {
+        let hazards = self.map.theme.theme().hazards();
+        for _ in 0 .. self.rooms.len() / 4 {
+            let room = self.rooms[rng.range(1, self.rooms.len())];
+            let center = Point::new(rng.range(room.x1, room.x2), rng.range(room.y1, room.y2));
+            let radius = rng.range(1.0, 3.0);
+            let tile = hazards[rng.range(0, hazards.len())];
+            room.for_each(|p| {
+                if DistanceAlg::Pythagoras.distance2d(center, p) <= radius {
+                    self.place_hazard(p, tile);
+                }
+            });
+        }
+
+        if rng.range(0, 2) == 0 {
+            let tile = hazards[rng.range(0, hazards.len())];
+            let tile = if tile == TileType::Chasm { hazards[0] } else { tile };
+            let mut x = rng.range(1, self.map.width - 1);
+            for y in 1 .. self.map.height - 1 {
+                self.place_hazard(Point::new(x, y), tile);
+                x = (x + rng.range(-1, 2)).clamp(1, self.map.width - 2);
+                self.place_hazard(Point::new(x, y), tile);
+            }
+        }
+    }
+
+    /*
+    Turns one floor tile into a hazard, unless that would cut the player off from the exit
+    or from any room. Tiles left as floor that way become the bridges and fords across a river.
+    Walls are left standing, so a hazard never opens a way or a line of sight through rock.
+    Room centers are where the player and monsters spawn, so they are never touched.
+    Flooding the whole map is slow on big maps, so it is only done for floor tiles that could cut a path at all.
+     */
+    fn place_hazard(&mut self, pos: Point, tile: TileType) {
+        let Some(idx) = self.map.try_idx(pos) else {
+            return;
+        };
+        let on_map_edge = pos.x == 0 || pos.y == 0 || pos.x == self.map.width - 1 || pos.y == self.map.height - 1;
+        if on_map_edge || self.map.tiles[idx] != TileType::Floor || self.rooms.iter().any(|r| r.center() == pos) {
+            return;
+        }
+
+        self.map.tiles[idx] = tile;
+        if self.could_cut_path(pos) {
+            let distances = self.safe_distances();
+            let reachable = |p: Point| distances[self.map.map_idx(p.x, p.y)].is_some();
+            if !reachable(self.exit) || !self.rooms.iter().all(|r| reachable(r.center())) {
+                self.map.tiles[idx] = TileType::Floor;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /*
+    A 10x5 map of rock with a passage rows tiles tall from the start at 1,2 to the exit at 8,2.
+     */
+    fn passage(rows: i32) -> MapBuilder {
+        let mut mb = MapBuilder {
+            map: Map::new(10, 5),
+            rooms: vec![Rect::with_size(1, 2, 1, 1)],
+            player_start: Point::new(1, 2),
+            exit: Point::new(8, 2),
+        };
+        mb.fill(TileType::Wall);
+        for y in 2 .. 2 + rows {
+            for x in 1 ..= 8 {
+                let idx = mb.map.map_idx(x, y);
+                mb.map.tiles[idx] = TileType::Floor;
+            }
+        }
+        let exit = mb.map.map_idx(8, 2);
+        mb.map.tiles[exit] = TileType::Exit;
+        mb
+    }
+
+    #[test]
+    fn hazards_never_cut_off_the_exit_or_a_room() {
+        for seed in 0 .. 20 {
+            let mb = MapBuilder::new(&mut RandomNumberGenerator::seeded(seed), 80, 50);
+            let distances = mb.safe_distances();
+            let reachable = |p: Point| distances[mb.map.map_idx(p.x, p.y)].is_some();
+            assert!(reachable(mb.exit), "seed {seed} cut off the exit");
+            assert!(mb.rooms.iter().all(|r| reachable(r.center())), "seed {seed} cut off a room");
+        }
+    }
+
+    #[test]
+    fn a_hazard_that_would_block_the_only_way_through_is_left_as_floor() {
+        let mut mb = passage(1);
+        mb.place_hazard(Point::new(4, 2), TileType::Lava);
+        assert!(mb.map.tile(Point::new(4, 2)) == Some(TileType::Floor));
+    }
+
+    #[test]
+    fn a_hazard_with_a_way_around_it_is_placed() {
+        let mut mb = passage(2);
+        mb.place_hazard(Point::new(4, 2), TileType::Lava);
+        assert!(mb.map.tile(Point::new(4, 2)) == Some(TileType::Lava));
+    }
+
+    #[test]
+    fn hazards_never_replace_walls() {
+        let mut mb = passage(1);
+        mb.place_hazard(Point::new(4, 1), TileType::DeepWater);
+        assert!(mb.map.tile(Point::new(4, 1)) == Some(TileType::Wall));
+        for seed in 0 .. 20 {
+            let mut rng = RandomNumberGenerator::seeded(seed);
+            let mut mb = MapBuilder::new(&mut rng, 80, 50);
+            let walls: Vec<usize> = (0 .. mb.map.tiles.len()).filter(|idx| mb.map.tiles[*idx] == TileType::Wall).collect();
+            mb.build_hazards(&mut rng);
+            assert!(walls.iter().all(|idx| mb.map.tiles[*idx] == TileType::Wall), "seed {seed} put a hazard in a wall");
+        }
+    }
+
+    #[test]
+    fn only_a_tile_between_two_separate_stretches_of_floor_could_cut_a_path() {
+        assert!(passage(1).could_cut_path(Point::new(4, 2)));
+        assert!(!passage(2).could_cut_path(Point::new(4, 2)));
+        // The end of a dead end has nothing beyond it to cut off.
+        assert!(!passage(1).could_cut_path(Point::new(1, 2)));
+    }
+}
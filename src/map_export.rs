@@ -0,0 +1,251 @@
+use crate::prelude::*;
+use image::{Rgb, RgbImage};
+use std::fs;
+use std::path::Path;
+
+// Each map tile is drawn as a square this many pixels wide.
+const PIXELS_PER_TILE: u32 = 8;
+
+/*
+Generates a batch of levels without opening a window, so the generator's output can be reviewed in bulk, e.g.
+    cargo run -- --export-maps exports 1 2 3
+writes exports/map-1.txt and exports/map-1.png, and the same for seeds 2 and 3.
+Each level is built and populated exactly as the first level of a game started with the same seed.
+ */
+pub fn export_maps_from_args(args: &[String]) -> BError {
+    let mut args = args.iter();
+    let out_dir = args.next().ok_or("--export-maps needs an output directory")?;
+    let seeds = args
+        .map(|arg| arg.parse::<u64>().map_err(|_| format!("{arg} is not a seed")))
+        .collect::<Result<Vec<_>, _>>()?;
+    if seeds.is_empty() {
+        return Err("--export-maps needs at least one seed".into());
+    }
+
+    fs::create_dir_all(out_dir)?;
+    for seed in seeds {
+        export_map(Path::new(out_dir), seed)?;
+    }
+    Ok(())
+}
+
+fn export_map(out_dir: &Path, seed: u64) -> BError {
+    let mut rng = RandomNumberGenerator::seeded(seed);
+    let map_builder = MapBuilder::new(&mut rng, MAP_WIDTH, MAP_HEIGHT);
+    let mut ecs = World::default();
+    spawn_level(&mut ecs, &mut rng, &map_builder);
+
+    let txt_path = out_dir.join(format!("map-{seed}.txt"));
+    let png_path = out_dir.join(format!("map-{seed}.png"));
+    fs::write(&txt_path, map_text(&map_builder, &ecs))?;
+    map_image(&map_builder, &ecs).save(&png_path)?;
+    println!(
+        "seed {seed}: {} rooms, {:?} theme -> {}, {}",
+        map_builder.rooms.len(),
+        map_builder.map.theme,
+        txt_path.display(),
+        png_path.display()
+    );
+    Ok(())
+}
+
+/*
+What was spawned where: monsters, traps (hidden or not), food and torches.
+ */
+#[derive(Clone, Copy)]
+enum Spawn {
+    Monster(FontCharType),
+    Trap,
+    Food,
+    Torch,
+}
+
+/*
+Everything the export knows how to show. Orcs carry a light too, but they are monsters first.
+Anything else that gets spawned is left out until it is given a Spawn of its own.
+ */
+fn spawns(ecs: &World) -> Vec<(Point, Spawn)> {
+    let mut query = <(&Point, &Render, Option<&Enemy>, Option<&Trap>, Option<&Food>, Option<&LightSource>)>::query();
+    query
+        .iter(ecs)
+        .filter_map(|(pos, render, enemy, trap, food, light)| {
+            let spawn = if enemy.is_some() {
+                Spawn::Monster(render.glyph)
+            } else if trap.is_some() {
+                Spawn::Trap
+            } else if food.is_some() {
+                Spawn::Food
+            } else if light.is_some() {
+                Spawn::Torch
+            } else {
+                return None;
+            };
+            Some((*pos, spawn))
+        })
+        .collect()
+}
+
+/*
+One character per tile, one line per row. Tiles are drawn by type rather than by theme, so every level reads the same:
+    # wall  . floor  + closed door  ' open door  > exit  ~ deep water  = lava  : chasm
+    @ player start  ^ trap  ! food  * torch  and monsters by their letter
+ */
+fn map_text(map_builder: &MapBuilder, ecs: &World) -> String {
+    let map = &map_builder.map;
+    let mut grid: Vec<char> = map
+        .tiles
+        .iter()
+        .map(|tile| match tile {
+            TileType::Wall => '#',
+            TileType::Floor => '.',
+            TileType::Door { open: false } => '+',
+            TileType::Door { open: true } => '\'',
+            TileType::Exit => '>',
+            TileType::DeepWater => '~',
+            TileType::Lava => '=',
+            TileType::Chasm => ':',
+        })
+        .collect();
+
+    for (pos, spawn) in spawns(ecs) {
+        grid[map.map_idx(pos.x, pos.y)] = match spawn {
+            Spawn::Monster(glyph) => ascii_glyph(glyph),
+            Spawn::Trap => '^',
+            Spawn::Food => '!',
+            Spawn::Torch => '*',
+        };
+    }
+    let start = map_builder.player_start;
+    grid[map.map_idx(start.x, start.y)] = '@';
+
+    #[allow(clippy::cast_sign_loss)]
+    let width = map.width as usize;
+    grid.chunks(width).fold(String::new(), |mut text, row| {
+        text.extend(row);
+        text.push('\n');
+        text
+    })
+}
+
+/*
+The map as a picture: tiles colored by type, rooms outlined in yellow, and a marker in the middle of each tile
+where something starts out. The player start is green, monsters red, traps magenta, food cyan and torches orange.
+ */
+#[allow(clippy::cast_sign_loss)]
+fn map_image(map_builder: &MapBuilder, ecs: &World) -> RgbImage {
+    let map = &map_builder.map;
+    let mut image = RgbImage::new(map.width as u32 * PIXELS_PER_TILE, map.height as u32 * PIXELS_PER_TILE);
+
+    for y in 0 .. map.height {
+        for x in 0 .. map.width {
+            let color = match map.tiles[map.map_idx(x, y)] {
+                TileType::Wall => DIM_GRAY,
+                TileType::Floor => LIGHT_GRAY,
+                TileType::Door { .. } => SADDLE_BROWN,
+                TileType::Exit => GOLD,
+                TileType::DeepWater => STEEL_BLUE,
+                TileType::Lava => ORANGE_RED,
+                TileType::Chasm => BLACK,
+            };
+            fill(&mut image, x as u32 * PIXELS_PER_TILE, y as u32 * PIXELS_PER_TILE, PIXELS_PER_TILE, color);
+        }
+    }
+
+    for room in &map_builder.rooms {
+        let (x1, y1) = (room.x1 as u32 * PIXELS_PER_TILE, room.y1 as u32 * PIXELS_PER_TILE);
+        let (x2, y2) = (room.x2 as u32 * PIXELS_PER_TILE - 1, room.y2 as u32 * PIXELS_PER_TILE - 1);
+        for x in x1 ..= x2 {
+            put(&mut image, x, y1, YELLOW);
+            put(&mut image, x, y2, YELLOW);
+        }
+        for y in y1 ..= y2 {
+            put(&mut image, x1, y, YELLOW);
+            put(&mut image, x2, y, YELLOW);
+        }
+    }
+
+    let mut mark = |pos: Point, color| {
+        let inset = PIXELS_PER_TILE / 4;
+        fill(
+            &mut image,
+            pos.x as u32 * PIXELS_PER_TILE + inset,
+            pos.y as u32 * PIXELS_PER_TILE + inset,
+            PIXELS_PER_TILE - inset * 2,
+            color,
+        );
+    };
+    for (pos, spawn) in spawns(ecs) {
+        mark(pos, match spawn {
+            Spawn::Monster(_) => RED,
+            Spawn::Trap => MAGENTA,
+            Spawn::Food => CYAN,
+            Spawn::Torch => ORANGE,
+        });
+    }
+    mark(map_builder.player_start, LIME_GREEN);
+
+    image
+}
+
+fn fill(image: &mut RgbImage, x: u32, y: u32, size: u32, color: (u8, u8, u8)) {
+    for py in y .. y + size {
+        for px in x .. x + size {
+            put(image, px, py, color);
+        }
+    }
+}
+
+// Pixels outside the picture are ignored, so rooms touching the map's edge can still be outlined.
+fn put(image: &mut RgbImage, x: u32, y: u32, color: (u8, u8, u8)) {
+    if x < image.width() && y < image.height() {
+        image.put_pixel(x, y, Rgb([color.0, color.1, color.2]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /*
+    A 4x3 room with the start on the left, the exit in the bottom right corner, a goblin, a pit trap,
+    a flask of broth, a torch, and a door key the export doesn't know about.
+     */
+    fn small_level() -> (MapBuilder, World) {
+        let mut map = Map::new(4, 3);
+        let (wall, exit) = (map.map_idx(3, 0), map.map_idx(3, 2));
+        map.tiles[wall] = TileType::Wall;
+        map.tiles[exit] = TileType::Exit;
+        let map_builder = MapBuilder {
+            map,
+            rooms: vec![Rect::with_size(0, 0, 4, 3)],
+            player_start: Point::new(0, 1),
+            exit: Point::new(3, 2),
+        };
+        let mut ecs = World::default();
+        let render = |c| Render { color: ColorPair::new(WHITE, BLACK), glyph: to_cp437(c) };
+        ecs.push((Enemy, Point::new(1, 1), render('g')));
+        ecs.push((Trap { kind: TrapKind::Pit }, Point::new(2, 1), render('^')));
+        ecs.push((Food { nutrition: 100 }, Point::new(0, 2), render('%')));
+        ecs.push((LightSource { radius: 3, color: RGB::named(ORANGE) }, Point::new(1, 2), render('/')));
+        ecs.push((Point::new(2, 2), render('k')));
+        (map_builder, ecs)
+    }
+
+    #[test]
+    fn the_text_shows_tiles_by_type_with_the_start_and_spawns_over_them() {
+        let (map_builder, ecs) = small_level();
+        assert_eq!(map_text(&map_builder, &ecs), "...#\n@g^.\n!*.>\n");
+    }
+
+    #[test]
+    fn the_picture_outlines_rooms_and_marks_the_start() {
+        let (map_builder, ecs) = small_level();
+        let image = map_image(&map_builder, &ecs);
+        let rgb = |(r, g, b)| Rgb([r, g, b]);
+        assert_eq!(image.dimensions(), (4 * PIXELS_PER_TILE, 3 * PIXELS_PER_TILE));
+        assert_eq!(*image.get_pixel(0, 0), rgb(YELLOW));
+        assert_eq!(*image.get_pixel(4, 12), rgb(LIME_GREEN));
+        assert_eq!(*image.get_pixel(12, 12), rgb(RED));
+        assert_eq!(*image.get_pixel(27, 2), rgb(DIM_GRAY));
+    }
+}
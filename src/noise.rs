@@ -0,0 +1,31 @@
+use crate::prelude::*;
+
+// How far the sounds of the dungeon carry.
+pub const COMBAT_NOISE: f32 = 8.0;
+pub const DOOR_NOISE: f32 = 6.0;
+pub const TRAP_NOISE: f32 = 6.0;
+pub const ALARM_NOISE: f32 = 10.0;
+
+/*
+Something loud enough for the monsters to hear, made at pos. Monsters further away than radius don't hear it.
+ */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Noise {
+    pub pos: Point,
+    pub radius: f32,
+}
+
+/*
+The noises made since the monsters last listened. Systems add to it whenever something makes a racket;
+the alertness system sends every monster in earshot to look where it came from, and clears it.
+ */
+#[derive(Default)]
+pub struct Noises {
+    pub heard: Vec<Noise>,
+}
+
+impl Noises {
+    pub fn make(&mut self, pos: Point, radius: f32) {
+        self.heard.push(Noise { pos, radius });
+    }
+}
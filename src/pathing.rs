@@ -0,0 +1,138 @@
+use crate::prelude::*;
+
+// Extra steps a mover is willing to walk to get around someone standing in the way, rather than wait for them.
+const OCCUPIED_PENALTY: f32 = 5.0;
+
+/*
+The map as one particular mover sees it, for bracket-lib's path finding.
+    Only the tiles the mover can actually walk onto lead anywhere: closed doors if it can open them, deep water
+    if it can swim. Lava and chasms are never walked into. Tiles with something standing on them can still be
+    walked through, at a price, so movers go around each other when there's room, and queue up when there isn't.
+ */
+pub struct PathingMap<'a> {
+    pub map: &'a Map,
+    pub index: &'a SpatialIndex,
+    pub opens_doors: bool,
+    pub can_swim: bool,
+}
+
+impl PathingMap<'_> {
+    /*
+    The first step of the shortest path from pos to target, if there is one.
+     */
+    pub fn step_towards(&self, pos: Point, target: Point) -> Option<Point> {
+        if pos == target {
+            return None;
+        }
+        let path = a_star_search(self.map.try_idx(pos)?, self.map.try_idx(target)?, self);
+        path.steps
+            .get(1)
+            .filter(|_| path.success)
+            .map(|step| self.map.index_to_point2d(*step))
+    }
+
+    fn step_cost(&self, destination: Point) -> Option<f32> {
+        let tile = self.map.tile(destination)?;
+        let cost = match tile {
+            TileType::Door { open: false } if self.opens_doors => 1,
+            TileType::Lava | TileType::Chasm => return None,
+            _ => tile.move_cost(self.can_swim)?,
+        };
+        i16::try_from(cost).ok().map(f32::from)
+    }
+}
+
+impl BaseMap for PathingMap<'_> {
+    fn is_opaque(&self, idx: usize) -> bool {
+        self.map.is_opaque(idx)
+    }
+
+    /*
+    Any of the eight neighbours the mover can walk onto without cutting a corner.
+    Diagonal steps cost a little more, to keep paths straight.
+     */
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits = SmallVec::new();
+        let from = self.map.index_to_point2d(idx);
+        for dy in -1 ..= 1 {
+            for dx in -1 ..= 1 {
+                let destination = from + Point::new(dx, dy);
+                if destination == from || self.map.cuts_corner(from, destination) {
+                    continue;
+                }
+                let Some(cost) = self.step_cost(destination) else {
+                    continue;
+                };
+                let to = self.map.map_idx(destination.x, destination.y);
+                let cost = if dx != 0 && dy != 0 { cost * 1.45 } else { cost };
+                let cost = if self.index.is_blocked(to) { cost + OCCUPIED_PENALTY } else { cost };
+                exits.push((to, cost));
+            }
+        }
+        exits
+    }
+
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        DistanceAlg::Pythagoras.distance2d(self.map.index_to_point2d(idx1), self.map.index_to_point2d(idx2))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /*
+    A 5x3 floor split down the middle by a wall with one gap, at 2,1, filled with gap.
+     */
+    fn split_map(gap: TileType) -> Map {
+        let mut map = Map::new(5, 3);
+        for y in 0 .. 3 {
+            let idx = map.map_idx(2, y);
+            map.tiles[idx] = if y == 1 { gap } else { TileType::Wall };
+        }
+        map
+    }
+
+    fn step(map: &Map, opens_doors: bool, can_swim: bool) -> Option<Point> {
+        let mut index = SpatialIndex::default();
+        index.clear(map);
+        let pathing = PathingMap { map, index: &index, opens_doors, can_swim };
+        pathing.step_towards(Point::new(0, 1), Point::new(4, 1))
+    }
+
+    #[test]
+    fn only_movers_that_open_doors_path_through_closed_ones() {
+        let map = split_map(TileType::Door { open: false });
+        assert_eq!(step(&map, false, false), None);
+        assert_eq!(step(&map, true, false), Some(Point::new(1, 1)));
+    }
+
+    #[test]
+    fn only_swimmers_path_through_deep_water() {
+        let map = split_map(TileType::DeepWater);
+        assert_eq!(step(&map, true, false), None);
+        assert!(step(&map, true, true).is_some());
+    }
+
+    #[test]
+    fn nobody_paths_through_lava() {
+        assert_eq!(step(&split_map(TileType::Lava), true, true), None);
+    }
+
+    #[test]
+    fn paths_go_around_an_occupied_tile_when_there_is_room() {
+        let map = Map::new(5, 3);
+        let mut world = World::default();
+        let mut index = SpatialIndex::default();
+        index.clear(&map);
+        index.add(world.push((BlocksTile,)), map.map_idx(2, 1), true);
+        let pathing = PathingMap { map: &map, index: &index, opens_doors: false, can_swim: false };
+
+        let (mut pos, target) = (Point::new(0, 1), Point::new(4, 1));
+        while let Some(next) = pathing.step_towards(pos, target) {
+            assert_ne!(next, Point::new(2, 1));
+            pos = next;
+        }
+        assert_eq!(pos, target);
+    }
+}
@@ -0,0 +1,170 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/*
+Everything needed to play a game again exactly as it went: the seed, the rules, keys and spells it was played with,
+and the keys the game acted on, in order. Games are recorded to replays/<seed>.ron as they are played,
+so a bug report can come with the game that led up to it.
+
+Only frames where the game was waiting for the player matter, and only those where a key was pressed
+or the turn moved on anyway (a busy player sits turns out without a key). Every other frame just draws,
+so replaying the recorded frames one after the other gives the same game, however fast it was played.
+
+world_hash is taken whenever the replay is saved, once the last recorded input has played out.
+Replaying the inputs must end on the same hash, or the game has desynced.
+ */
+#[derive(Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub settings: Settings,
+    pub keymap: HashMap<Action, Vec<String>>,
+    // Replays recorded before spells were in their own file were played with the default ones.
+    #[serde(default)]
+    pub spellbook: Spellbook,
+    // Key names as in keymap.ron. Keys the keymap file can't name are recorded as None; the game ignores them anyway.
+    pub inputs: Vec<Option<String>>,
+    pub world_hash: u64,
+}
+
+impl Replay {
+    pub fn new(seed: u64, settings: Settings, keymap: &Keymap, spellbook: &Spellbook) -> Self {
+        Self {
+            seed,
+            settings,
+            keymap: keymap.to_names(),
+            spellbook: spellbook.clone(),
+            inputs: Vec::new(),
+            world_hash: 0,
+        }
+    }
+
+    pub fn record(&mut self, key: Option<VirtualKeyCode>) {
+        let name = key.map(|key| format!("{key:?}")).filter(|name| key_from_name(name).is_some());
+        self.inputs.push(name);
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Option<VirtualKeyCode>> + '_ {
+        self.inputs.iter().map(|name| name.as_deref().and_then(key_from_name))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|e| e.to_string())?;
+        fs::write(path, text).map_err(|e| e.to_string())
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        ron::from_str(&text).map_err(|e| e.to_string())
+    }
+}
+
+/*
+A fingerprint of the game: the map, whose turn it is, and the gameplay components of every entity.
+Entity ids aren't part of it, since they differ from one run to the next; entities are hashed in query order instead,
+which only depends on what happened in the game. New gameplay components should be added to the query,
+with a Fingerprint of their own (see fingerprint.rs).
+ */
+pub fn world_hash(ecs: &World, resources: &Resources) -> u64 {
+    let mut hasher = Fnv1a::new();
+    if let Some(map) = resources.get::<Map>() {
+        map.tiles.fingerprint(&mut hasher);
+        map.revealed_tiles.fingerprint(&mut hasher);
+        map.theme.fingerprint(&mut hasher);
+    }
+    if let Some(turn_state) = resources.get::<TurnState>() {
+        turn_state.fingerprint(&mut hasher);
+    }
+
+    let mut entities = <(
+        Option<&Point>,
+        Option<&Name>,
+        Option<&Health>,
+        Option<&Player>,
+        Option<&Busy>,
+        Option<&Trap>,
+        Option<&Hidden>,
+        Option<&Alertness>,
+    )>::query();
+    entities.iter(ecs).for_each(|(pos, name, health, player, busy, trap, hidden, alertness)| {
+        pos.fingerprint(&mut hasher);
+        name.fingerprint(&mut hasher);
+        health.fingerprint(&mut hasher);
+        player.fingerprint(&mut hasher);
+        busy.fingerprint(&mut hasher);
+        trap.fingerprint(&mut hasher);
+        hidden.fingerprint(&mut hasher);
+        alertness.fingerprint(&mut hasher);
+    });
+    // A query takes at most eight components, so the rest get one of their own.
+    let mut more = <(
+        Option<&Mana>,
+        Option<&Poisoned>,
+        Option<&Confused>,
+        Option<&Hasted>,
+        Option<&Slowed>,
+        Option<&Initiative>,
+        Option<&Experience>,
+        Option<&Attributes>,
+    )>::query();
+    more.iter(ecs).for_each(|(mana, poisoned, confused, hasted, slowed, initiative, experience, attributes)| {
+        mana.fingerprint(&mut hasher);
+        poisoned.fingerprint(&mut hasher);
+        confused.fingerprint(&mut hasher);
+        hasted.fingerprint(&mut hasher);
+        slowed.fingerprint(&mut hasher);
+        initiative.fingerprint(&mut hasher);
+        experience.fingerprint(&mut hasher);
+        attributes.fingerprint(&mut hasher);
+    });
+    let mut still_more = <(Option<&Hunger>, Option<&Food>)>::query();
+    still_more.iter(ecs).for_each(|(hunger, food)| {
+        hunger.fingerprint(&mut hasher);
+        food.fingerprint(&mut hasher);
+    });
+    hasher.finish()
+}
+
+/*
+The replay of the game being played, and where it is saved.
+ */
+pub struct Recording {
+    replay: Replay,
+    path: PathBuf,
+    unsaved: bool,
+}
+
+impl Recording {
+    pub fn new(seed: u64, settings: Settings, keymap: &Keymap, spellbook: &Spellbook) -> Self {
+        Self {
+            replay: Replay::new(seed, settings, keymap, spellbook),
+            path: PathBuf::from(format!("replays/{seed}.ron")),
+            unsaved: false,
+        }
+    }
+
+    pub fn record(&mut self, key: Option<VirtualKeyCode>) {
+        self.replay.record(key);
+        self.unsaved = true;
+    }
+
+    /*
+    Saves the replay if anything was recorded since the last save. Call it only while the game waits for the player,
+    so the world hash is taken once the last input has played out. A failed save is reported but doesn't stop the game.
+     */
+    pub fn save_if_unsaved(&mut self, world_hash: impl FnOnce() -> u64) {
+        if !self.unsaved {
+            return;
+        }
+        self.replay.world_hash = world_hash();
+        if let Err(e) = self.replay.save(&self.path) {
+            eprintln!("Could not save replay to {}: {e}", self.path.display());
+        }
+        self.unsaved = false;
+    }
+}
@@ -0,0 +1,66 @@
+use crate::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/*
+Optional game rules, read from resources/settings.ron.
+Any setting left out of the file keeps its default value.
+ */
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    // A diagonal step is refused when the two tiles beside it are both walls, so nothing squeezes between corners.
+    pub block_diagonal_corners: bool,
+    // Waiting a turn restores a hit point, as long as no enemy is in sight.
+    pub rest_heals: bool,
+    // The camera only scrolls when the player comes this many tiles close to the edge of the view. None keeps the player centered.
+    pub camera_dead_zone: Option<i32>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            block_diagonal_corners: true,
+            rest_heals: true,
+            camera_dead_zone: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn load(path: &str) -> Self {
+        match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ron::from_str::<Self>(&text).map_err(|e| e.to_string()))
+        {
+            Ok(settings) => settings.clamped(),
+            Err(e) => {
+                eprintln!("Using default settings, could not load {path}: {e}");
+                Self::default()
+            }
+        }
+    }
+
+    /*
+    A dead zone reaching the middle of the view leaves nowhere for the player to stand without the camera scrolling,
+    so it is cut down to just short of that.
+     */
+    fn clamped(mut self) -> Self {
+        self.camera_dead_zone = self.camera_dead_zone.map(|margin| margin.clamp(0, DISPLAY_WIDTH / 2 - 1));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_zones_are_cut_down_to_fit_the_view() {
+        let settings = |dead_zone| Settings { camera_dead_zone: dead_zone, ..Settings::default() }.clamped();
+        assert_eq!(settings(Some(100)).camera_dead_zone, Some(DISPLAY_WIDTH / 2 - 1));
+        assert_eq!(settings(Some(-3)).camera_dead_zone, Some(0));
+        assert_eq!(settings(Some(6)).camera_dead_zone, Some(6));
+        assert_eq!(settings(None).camera_dead_zone, None);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::prelude::*;
+
+/*
+Draws the whole map and everything on it as plain text, one line per map row, the way map_render and entity_render
+would draw it but without any bracket-lib context. Colors are left out, so lava, deep water and open doors all show
+as floor, just like the glyphs on screen. Snapshots are handy for comparing two runs of the same seed and keys.
+Tiles the player hasn't revealed are blank. Entities show only where the player can see them: in view, lit and not hidden.
+ */
+pub fn snapshot(ecs: &World, map: &Map, lighting: &Lighting) -> String {
+    let theme = map.theme.theme();
+    let mut grid: Vec<char> = map
+        .tiles
+        .iter()
+        .zip(&map.revealed_tiles)
+        .map(|(tile, revealed)| if *revealed { ascii_glyph(theme.tile_to_render(*tile).0) } else { ' ' })
+        .collect();
+
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    if let Some(player_fov) = fov.iter(ecs).next() {
+        // Entities are drawn in the order entity_render queries them, so the same one ends up on top.
+        <(&Point, &Render)>::query()
+            .filter(!component::<Hidden>())
+            .iter(ecs)
+            .filter(|(pos, _)| player_fov.visible_tiles.contains(pos) && lighting.is_lit(map, **pos))
+            .for_each(|(pos, render)| {
+                if let Some(idx) = map.try_idx(*pos) {
+                    grid[idx] = ascii_glyph(render.glyph);
+                }
+            });
+    }
+
+    #[allow(clippy::cast_sign_loss)]
+    let width = map.width as usize;
+    grid.chunks(width).fold(String::new(), |mut text, row| {
+        text.extend(row);
+        text.push('\n');
+        text
+    })
+}
+
+/*
+A character a plain terminal or text file can show for a glyph.
+dungeonfont.png draws pictures over a few ASCII characters; those that would read as something else in plain text
+get the usual roguelike symbol instead. Everything outside ASCII falls back to the closest ASCII shape.
+ */
+pub fn ascii_glyph(glyph: FontCharType) -> char {
+    let c = u8::try_from(glyph).map_or('?', to_char);
+    match c {
+        // Closed doors.
+        ';' => '+',
+        // Torches.
+        '/' => '*',
+        '\0' => ' ',
+        c if c.is_ascii() => c,
+        '─' | '═' => '-',
+        '│' | '║' => '|',
+        '·' | '∙' => '.',
+        '░' | '▒' | '▓' | '█' => '#',
+        c if ('┌'..='╬').contains(&c) => '+',
+        _ => '?',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_glyph_spells_out_the_font_specific_glyphs() {
+        assert_eq!(ascii_glyph(to_cp437(';')), '+');
+        assert_eq!(ascii_glyph(to_cp437('/')), '*');
+        assert_eq!(ascii_glyph(0), ' ');
+        assert_eq!(ascii_glyph(to_cp437('@')), '@');
+        assert_eq!(ascii_glyph(to_cp437('─')), '-');
+        assert_eq!(ascii_glyph(to_cp437('║')), '|');
+        assert_eq!(ascii_glyph(to_cp437('▓')), '#');
+        assert_eq!(ascii_glyph(to_cp437('╔')), '+');
+        assert_eq!(ascii_glyph(1000), '?');
+    }
+}
@@ -0,0 +1,155 @@
+use crate::prelude::*;
+use crate::State;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+// A game is called off after this many inputs, however well the bot is doing.
+const MAX_INPUTS: usize = 5000;
+// The bot should reveal something new or go deeper every so often; if it doesn't, it is going round in circles.
+const MAX_IDLE_INPUTS: usize = 500;
+// A turn that takes more frames than this to hand control back to the player never will.
+const MAX_FRAMES_PER_INPUT: usize = 1000;
+
+/*
+Lets the bot play one game after another, e.g.
+    cargo run --release -- --soak 1 1000
+plays seeds 1 to 1000. Games that panic, hang, or leave the bot with nowhere to go are reported,
+and their replays saved to replays/soak-<seed>.ron so they can be played back with --replay.
+ */
+pub fn soak_from_args(first_seed: Option<&String>, count: Option<&String>) -> BError {
+    let first_seed = first_seed.ok_or("--soak needs a first seed")?.parse::<u64>()?;
+    let count = count.map_or(Ok(100), |count| count.parse::<u64>())?;
+
+    let (mut deaths, mut deepest, mut problems) = (0, 0, 0);
+    for seed in first_seed .. first_seed + count {
+        match soak_game(seed) {
+            Ok(game) => {
+                deaths += u32::from(game.died);
+                deepest = deepest.max(game.depth);
+            }
+            Err(problem) => {
+                problems += 1;
+                println!("seed {seed}: {problem}");
+            }
+        }
+    }
+    println!("Played {count} games: {deaths} died, deepest level {deepest}, {problems} with problems");
+    if problems > 0 {
+        return Err(format!("{problems} games had problems").into());
+    }
+    Ok(())
+}
+
+pub struct SoakGame {
+    pub depth: u32,
+    pub level: i32,
+    pub died: bool,
+    pub stats: GameStats,
+}
+
+/*
+Plays one game with the bot. Any problem comes back as a description, with the replay saved next to it.
+ */
+pub fn soak_game(seed: u64) -> Result<SoakGame, String> {
+    let mut state = State::seeded(seed);
+    let mut replay = Replay::new(
+        seed,
+        *state.resources.get::<Settings>().unwrap(),
+        &state.resources.get::<Keymap>().unwrap(),
+        &state.resources.get::<Spellbook>().unwrap(),
+    );
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        state.first_frame();
+        play(&mut state, &mut replay)
+    }));
+    let problem = match result {
+        Ok(Ok(game)) => return Ok(game),
+        Ok(Err(problem)) => {
+            state.play_until_input();
+            replay.world_hash = world_hash(&state.ecs, &state.resources);
+            problem
+        }
+        Err(panic) => {
+            let message = panic
+                .downcast_ref::<&str>()
+                .map(ToString::to_string)
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            format!("panicked: {message}")
+        }
+    };
+
+    let path = format!("replays/soak-{seed}.ron");
+    match replay.save(Path::new(&path)) {
+        Ok(()) => Err(format!("{problem} (replay in {path})")),
+        Err(e) => Err(format!("{problem} (could not save the replay: {e})")),
+    }
+}
+
+fn play(state: &mut State, replay: &mut Replay) -> Result<SoakGame, String> {
+    let mut progress = progress(state);
+    let mut idle_inputs = 0;
+    let mut waited = false;
+    let mut bot = Bot::default();
+    for _ in 0 .. MAX_INPUTS {
+        let mut frames = 0;
+        while !state.waiting_for_player() {
+            state.step(None);
+            clear_command_buffer().expect("Render Error");
+            frames += 1;
+            if frames > MAX_FRAMES_PER_INPUT {
+                return Err(format!("the game never got back to the player after {frames} frames"));
+            }
+        }
+        if *state.resources.get::<TurnState>().unwrap() == TurnState::GameOver {
+            return Ok(finished(state, true));
+        }
+
+        // Right after a level change the player hasn't looked around yet, so the bot sits out a frame
+        // without a key, as the window would, before deciding it is stuck.
+        let key = match bot.choose_action(&state.ecs, &state.resources) {
+            Some(action) => {
+                waited = false;
+                let keymap = state.resources.get::<Keymap>().unwrap();
+                Some(keymap.key_for(action).ok_or(format!("no key is bound to {action:?}"))?)
+            }
+            None if !waited => {
+                waited = true;
+                None
+            }
+            None => return Err("the bot has nothing left to explore and no way down".to_string()),
+        };
+        replay.record(key);
+        state.step(key);
+        clear_command_buffer().expect("Render Error");
+
+        let now = self::progress(state);
+        if now == progress {
+            idle_inputs += 1;
+            if idle_inputs > MAX_IDLE_INPUTS {
+                return Err(format!("the bot got nowhere in {MAX_IDLE_INPUTS} inputs"));
+            }
+        } else {
+            progress = now;
+            idle_inputs = 0;
+        }
+    }
+    Ok(finished(state, false))
+}
+
+fn finished(state: &State, died: bool) -> SoakGame {
+    SoakGame {
+        depth: progress(state).0,
+        level: <&Experience>::query().iter(&state.ecs).next().map_or(1, |experience| experience.level),
+        died,
+        stats: state.resources.get::<GameStats>().unwrap().clone(),
+    }
+}
+
+// How deep the player is, and how much of the level they have seen.
+fn progress(state: &State) -> (u32, usize) {
+    let depth = <&Player>::query().iter(&state.ecs).next().map_or(0, |player| player.map_level);
+    let revealed = state.resources.get::<Map>().unwrap().revealed_tiles.iter().filter(|r| **r).count();
+    (depth, revealed)
+}
@@ -0,0 +1,107 @@
+use crate::prelude::*;
+
+/*
+Which entities stand on each tile of the map, stored by map index so looking up a tile doesn't mean scanning every entity.
+The spatial_index system rebuilds it at the start of each turn, and the systems that move things keep it up to date
+as they go. Systems that remove an entity from the map take it out of the index too, so it never lists the dead.
+ */
+#[derive(Default)]
+pub struct SpatialIndex {
+    tile_content: Vec<Vec<Entity>>,
+    // The entities on each tile that block movement.
+    blockers: Vec<Vec<Entity>>,
+}
+
+impl SpatialIndex {
+    /*
+    Empties the index, resizing it to fit the map if the level changed size.
+     */
+    pub fn clear(&mut self, map: &Map) {
+        let num_tiles = map.tiles.len();
+        self.tile_content.resize_with(num_tiles, Vec::new);
+        self.tile_content.iter_mut().for_each(Vec::clear);
+        self.blockers.resize_with(num_tiles, Vec::new);
+        self.blockers.iter_mut().for_each(Vec::clear);
+    }
+
+    pub fn add(&mut self, entity: Entity, idx: usize, blocks_tile: bool) {
+        self.tile_content[idx].push(entity);
+        if blocks_tile {
+            self.blockers[idx].push(entity);
+        }
+    }
+
+    /*
+    Takes the entity off the tile at idx, for an entity that was killed or otherwise removed from the map.
+     */
+    pub fn remove(&mut self, entity: Entity, idx: usize) {
+        self.tile_content[idx].retain(|e| *e != entity);
+        self.blockers[idx].retain(|e| *e != entity);
+    }
+
+    pub fn move_entity(&mut self, entity: Entity, from: usize, to: usize) {
+        let blocks_tile = self.blockers[from].contains(&entity);
+        self.remove(entity, from);
+        self.add(entity, to, blocks_tile);
+    }
+
+    pub fn entities_at(&self, idx: usize) -> &[Entity] {
+        &self.tile_content[idx]
+    }
+
+    /*
+    Whether something that blocks movement stands on the tile.
+     */
+    pub fn is_blocked(&self, idx: usize) -> bool {
+        !self.blockers[idx].is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_with_two_entities() -> (SpatialIndex, Entity, Entity) {
+        let mut world = World::default();
+        let (boulder, mouse) = (world.push((BlocksTile,)), world.push((Enemy,)));
+        let mut index = SpatialIndex::default();
+        index.clear(&Map::new(3, 3));
+        (index, boulder, mouse)
+    }
+
+    #[test]
+    fn a_tile_is_blocked_only_while_a_blocker_stands_on_it() {
+        let (mut index, boulder, mouse) = index_with_two_entities();
+        index.add(mouse, 4, false);
+        assert!(!index.is_blocked(4));
+        index.add(boulder, 4, true);
+        assert!(index.is_blocked(4));
+        assert_eq!(index.entities_at(4), &[mouse, boulder]);
+
+        index.move_entity(boulder, 4, 5);
+        assert!(!index.is_blocked(4));
+        assert!(index.is_blocked(5));
+        assert_eq!(index.entities_at(4), &[mouse]);
+    }
+
+    #[test]
+    fn removing_something_that_doesnt_block_leaves_the_tile_blocked() {
+        let (mut index, boulder, mouse) = index_with_two_entities();
+        index.add(boulder, 4, true);
+        index.add(mouse, 4, false);
+        index.remove(mouse, 4);
+        assert!(index.is_blocked(4));
+        assert_eq!(index.entities_at(4), &[boulder]);
+        index.remove(boulder, 4);
+        assert!(!index.is_blocked(4));
+    }
+
+    #[test]
+    fn clearing_fits_the_index_to_a_new_map() {
+        let (mut index, boulder, _) = index_with_two_entities();
+        index.add(boulder, 8, true);
+        index.clear(&Map::new(5, 5));
+        assert!(!index.is_blocked(8));
+        assert!(index.entities_at(24).is_empty());
+    }
+}
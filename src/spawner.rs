@@ -6,36 +6,229 @@ The function requires a mutable reference to the World and the location in which
 Calling push() creates a new entity composed of the listed components.
 This adds a tag component, indicating that this is the player.
     Tag components are treated like other components.
+A tuple holds at most eight components, so the player's abilities, lantern, sling, mana, speed, attributes, experience
+and hunger are added to the new entity afterwards.
  */
 pub fn spawn_player(ecs: &mut World, pos: Point) {
-    ecs.push(
+    let player = ecs.push(
         (
-            Player,
+            Player { map_level: 0 },
+            Name("Adventurer".to_string()),
             pos,
             Render {
                 color: ColorPair::new(WHITE, BLACK),
                 glyph: to_cp437('@')
-            }
+            },
+            Health { current: 20, max: 20 },
+            FieldOfView::new(8),
+            BlocksTile,
+            Damage(3),
         )
     );
+
+    let mut entry = ecs.entry(player).unwrap();
+    entry.add_component(OpensDoors);
+    entry.add_component(Swimmer);
+    entry.add_component(LightSource { radius: 5, color: RGB::named(LIGHT_YELLOW) });
+    entry.add_component(RangedWeapon { damage: 2, range: 6 });
+    entry.add_component(Mana { current: 10, max: 10 });
+    entry.add_component(Initiative::new(ACTION_COST));
+    entry.add_component(Attributes { strength: 0, agility: 0, toughness: 0 });
+    entry.add_component(Experience { level: 1, xp: 0, perks_to_pick: 0 });
+    entry.add_component(Hunger { satiety: 1000, max: 1000 });
+}
+
+/*
+Everything that sets one kind of monster apart from the others. Tougher monsters have more hit points and hit harder.
+ */
+pub struct MonsterTemplate {
+    pub name: &'static str,
+    pub glyph: char,
+    pub hp: i32,
+    // Hit points taken from the player with every blow.
+    pub damage: i32,
+    pub opens_doors: bool,
+    pub carries_torch: bool,
+    // Energy gained every turn; the player's is ACTION_COST.
+    pub speed: i32,
+    // Earned by whoever kills it.
+    pub xp: i32,
 }
 
+/*
+The monsters that roam the dungeon. The brutes can't work a door handle; orcs and goblins can.
+Orcs carry torches, so they can be seen coming from afar. Goblins scurry about twice as fast as the player;
+lumbering ettins only move every other turn.
+ */
+pub const MONSTERS: [MonsterTemplate; 4] = [
+    MonsterTemplate { name: "Ettin", glyph: 'E', hp: 10, damage: 3, opens_doors: false, carries_torch: false, speed: 50, xp: 10 },
+    MonsterTemplate { name: "Ogre", glyph: 'O', hp: 4, damage: 2, opens_doors: false, carries_torch: false, speed: 100, xp: 5 },
+    MonsterTemplate { name: "Orc", glyph: 'o', hp: 2, damage: 1, opens_doors: true, carries_torch: true, speed: 100, xp: 3 },
+    MonsterTemplate { name: "Goblin", glyph: 'g', hp: 1, damage: 1, opens_doors: true, carries_torch: false, speed: 200, xp: 2 },
+];
+
 pub fn spawn_monster(ecs: &mut World,
                      rng: &mut RandomNumberGenerator,
                      pos: Point) {
-    ecs.push(
+    //the spawning code randomly picks one of the monster templates.
+    let template = &MONSTERS[rng.range(0, MONSTERS.len())];
+
+    let monster = ecs.push(
         (Enemy,
+            Name(template.name.to_string()),
             pos,
             Render {
                 color: ColorPair::new(WHITE, BLACK),
-                //the spawning code randomly selects one of four monster types
-                glyph: match rng.range(0,4) {
-                    0 => to_cp437('E'),
-                    1 => to_cp437('O'),
-                    2 => to_cp437('o'),
-                    _ => to_cp437('g'),
-                }
-            }
+                glyph: to_cp437(template.glyph),
+            },
+            Health { current: template.hp, max: template.hp },
+            FieldOfView::new(6),
+            BlocksTile,
+            Damage(template.damage),
         )
     );
-}
\ No newline at end of file
+    ecs.entry(monster).unwrap().add_component(Initiative::new(template.speed));
+    ecs.entry(monster).unwrap().add_component(XpReward(template.xp));
+    if template.opens_doors {
+        ecs.entry(monster).unwrap().add_component(OpensDoors);
+    }
+    if template.carries_torch {
+        ecs.entry(monster).unwrap().add_component(LightSource { radius: 3, color: RGB::named(ORANGE) });
+    }
+    //One monster in three is found asleep; the others wander about until they notice the player.
+    let alertness = if rng.range(0, 3) == 0 { Alertness::Asleep } else { Alertness::Wandering };
+    ecs.entry(monster).unwrap().add_component(alertness);
+}
+
+/*
+Traps start out hidden; the player has to search for them, or get lucky and notice one while walking past.
+ */
+pub fn spawn_trap(ecs: &mut World, rng: &mut RandomNumberGenerator, pos: Point) {
+    let (kind, name) = match rng.range(0, 4) {
+        0 => (TrapKind::Dart, "Dart trap"),
+        1 => (TrapKind::Teleport, "Teleport trap"),
+        2 => (TrapKind::Alarm, "Alarm trap"),
+        _ => (TrapKind::Pit, "Pit trap"),
+    };
+
+    ecs.push(
+        (Trap { kind },
+            Hidden,
+            Name(name.to_string()),
+            pos,
+            // A trap is drawn as a tinted patch of floor once it has been found.
+            Render {
+                color: ColorPair::new(MAGENTA, BLACK),
+                glyph: to_cp437('.'),
+            },
+        )
+    );
+}
+
+/*
+Every level gets one trap for every three rooms, on a random floor tile of a random room.
+The first room is kept safe, as are room centers, where the player and monsters spawn.
+ */
+pub fn spawn_traps(ecs: &mut World, rng: &mut RandomNumberGenerator, map: &Map, rooms: &[Rect]) {
+    if rooms.len() < 2 {
+        return;
+    }
+    for _ in 0 .. rooms.len() / 3 {
+        let room = rooms[rng.range(1, rooms.len())];
+        let pos = Point::new(rng.range(room.x1, room.x2), rng.range(room.y1, room.y2));
+        if map.tile(pos) == Some(TileType::Floor) && pos != room.center() {
+            spawn_trap(ecs, rng, pos);
+        }
+    }
+}
+
+/*
+A flask of broth to keep the player going. It is the only food in the dungeon, for now.
+ */
+pub fn spawn_food(ecs: &mut World, pos: Point) {
+    ecs.push(
+        (Name("Flask of broth".to_string()),
+            pos,
+            Render {
+                color: ColorPair::new(WHITE, BLACK),
+                glyph: to_cp437('!'),
+            },
+            Food { nutrition: 300 },
+        )
+    );
+}
+
+/*
+Every level gets a flask for every six rooms, on a random floor tile of a random room. Room centers are kept free,
+so the food doesn't end up under a monster.
+ */
+pub fn spawn_foods(ecs: &mut World, rng: &mut RandomNumberGenerator, map: &Map, rooms: &[Rect]) {
+    for _ in 0 .. rooms.len() / 6 {
+        let room = rooms[rng.range(0, rooms.len())];
+        let pos = Point::new(rng.range(room.x1, room.x2), rng.range(room.y1, room.y2));
+        if map.tile(pos) == Some(TileType::Floor) && pos != room.center() {
+            spawn_food(ecs, pos);
+        }
+    }
+}
+
+/*
+A torch burns in a wall, lighting up the room next to it.
+ */
+pub fn spawn_torch(ecs: &mut World, pos: Point) {
+    ecs.push(
+        (Name("Torch".to_string()),
+            pos,
+            Render {
+                color: ColorPair::new(ORANGE, BLACK),
+                glyph: to_cp437('/'),
+            },
+            LightSource { radius: 6, color: RGB::named(ORANGE) },
+        )
+    );
+}
+
+/*
+Half of the rooms get a torch, in a random spot of their top wall.
+The first room always gets one, so the level doesn't start out in the dark.
+ */
+pub fn spawn_torches(ecs: &mut World, rng: &mut RandomNumberGenerator, map: &Map, rooms: &[Rect]) {
+    for (i, room) in rooms.iter().enumerate() {
+        if i > 0 && rng.range(0, 2) == 0 {
+            continue;
+        }
+        let pos = Point::new(rng.range(room.x1, room.x2), room.y1 - 1);
+        if map.tile(pos) == Some(TileType::Wall) {
+            spawn_torch(ecs, pos);
+        }
+    }
+}
+
+/*
+Fills a freshly built level with everything that lives in it.
+ */
+pub fn spawn_level(ecs: &mut World, rng: &mut RandomNumberGenerator, map_builder: &MapBuilder) {
+    spawn_monsters(ecs, rng, &map_builder.rooms);
+    spawn_traps(ecs, rng, &map_builder.map, &map_builder.rooms);
+    spawn_torches(ecs, rng, &map_builder.map, &map_builder.rooms);
+    spawn_foods(ecs, rng, &map_builder.map, &map_builder.rooms);
+}
+
+/*
+Spawn one Monster per room, except in the first room with the player, where none will spawn.
+The Rect structure we used to place rooms includes a center() function.
+We can combine this with a bit of iterator magic to efficiently place a random monster in each room
+ */
+pub fn spawn_monsters(ecs: &mut World, rng: &mut RandomNumberGenerator, rooms: &[Rect]) {
+    rooms
+        .iter()
+        .skip(1)
+        // This transforms each entry from a room to the result of center() (a Point) using map().
+        // Mapping an iterator passes each entry into a closure, returning a different type of result.
+        // We can use map() to transform one type of iterator into another.
+        // After this call, we iterate a list of Point data representing the center of each room.
+        .map(Rect::center)
+        // This calls for_each to run a closure on each location.
+        // The closure receives the point as pos and calls our spawn_monster() function with the location.
+        .for_each(|pos| spawn_monster(ecs, rng, pos));
+}
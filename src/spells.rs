@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/*
+A spell the player can cast, read from resources/spells.ron.
+Every spell is aimed like a ranged weapon: it flies up to range tiles and comes down on the first thing in its way.
+Its effect then reaches every creature within radius tiles of that point that the point can see,
+so a radius of 0 only affects whatever was hit. Spells with a range of 0 aren't aimed; the caster casts them on themselves.
+ */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Spell {
+    pub name: String,
+    pub mana: i32,
+    pub range: i32,
+    #[serde(default)]
+    pub radius: i32,
+    pub effect: SpellEffect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SpellEffect {
+    // Takes this many hit points.
+    Damage(i32),
+    // Leaves its victims confused for this many turns.
+    Confuse(i32),
+    // Poisons its victims: they lose damage hit points at the end of each of the next turns turns.
+    Poison { damage: i32, turns: i32 },
+    // Lets its victims act twice a turn for this many turns. It cures Slow.
+    Haste(i32),
+    // Lets its victims act only every other turn, for this many turns. It cures Haste.
+    Slow(i32),
+    // Moves the caster to where the spell comes down, or as close to it as there is room.
+    Blink,
+}
+
+/*
+Every spell the player knows, in the order the cast menu lists them.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Spellbook {
+    pub spells: Vec<Spell>,
+}
+
+impl Default for Spellbook {
+    fn default() -> Self {
+        let spell = |name: &str, mana, range, radius, effect| Spell { name: name.to_string(), mana, range, radius, effect };
+        Self {
+            spells: vec![
+                spell("Magic missile", 2, 8, 0, SpellEffect::Damage(3)),
+                spell("Fireball", 5, 8, 2, SpellEffect::Damage(4)),
+                spell("Confusion", 3, 6, 1, SpellEffect::Confuse(5)),
+                spell("Poison cloud", 4, 8, 1, SpellEffect::Poison { damage: 1, turns: 6 }),
+                spell("Slow", 3, 6, 0, SpellEffect::Slow(8)),
+                spell("Haste", 5, 0, 0, SpellEffect::Haste(8)),
+                spell("Blink", 4, 6, 0, SpellEffect::Blink),
+            ],
+        }
+    }
+}
+
+impl Spellbook {
+    /*
+    Like the settings, a missing or broken spells file falls back to the default spells.
+     */
+    pub fn load(path: &str) -> Self {
+        match fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|e| e.to_string()))
+        {
+            Ok(spellbook) => spellbook,
+            Err(e) => {
+                eprintln!("Using default spells, could not load {path}: {e}");
+                Self::default()
+            }
+        }
+    }
+}
+
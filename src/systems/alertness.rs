@@ -0,0 +1,43 @@
+use crate::prelude::*;
+
+// Monster actions spent looking for the player before giving up and wandering off.
+const SEARCH_TURNS: i32 = 20;
+
+/*
+    Runs at the start of the monster schedule, and works out what every monster knows about the player.
+    A monster that is awake and has the player in its own field of view, in the light, chases them.
+    One that was chasing and has lost sight of them goes searching where it last saw them.
+    Any other monster that hears a noise, including a sleeping one, goes searching where it came from.
+    The noises have then been heard, and are cleared.
+ */
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[write_component(Alertness)]
+pub fn alertness(ecs: &mut SubWorld, #[resource] map: &Map, #[resource] lighting: &Lighting, #[resource] noises: &mut Noises) {
+    let mut player = <&Point>::query().filter(component::<Player>());
+    let player_pos = player.iter(ecs).next().copied();
+
+    let mut monsters = <(&Point, &mut Alertness, Option<&FieldOfView>)>::query().filter(!component::<Player>());
+    monsters.for_each_mut(ecs, |(pos, alertness, fov)| {
+        let seen = player_pos.filter(|player| {
+            *alertness != Alertness::Asleep
+                && fov.is_some_and(|fov| fov.visible_tiles.contains(player))
+                && lighting.is_lit(map, *player)
+        });
+        let heard = noises
+            .heard
+            .iter()
+            .rfind(|noise| DistanceAlg::Pythagoras.distance2d(*pos, noise.pos) <= noise.radius);
+
+        *alertness = match (*alertness, seen, heard) {
+            (_, Some(target), _) => Alertness::Chasing { target },
+            (Alertness::Chasing { target }, None, _) => Alertness::Searching { target, turns: SEARCH_TURNS },
+            (_, None, Some(noise)) => Alertness::Searching { target: noise.pos, turns: SEARCH_TURNS },
+            (unchanged, None, None) => unchanged,
+        };
+    });
+
+    noises.heard.clear();
+}
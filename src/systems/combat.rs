@@ -0,0 +1,193 @@
+use crate::prelude::*;
+
+/*
+    Runs once for every WantsToAttack message: the blow takes its damage from the victim's hit points.
+    The attacker's strength adds to the damage and the victim's toughness takes from it, but a blow always does at least one.
+    Monsters that run out are removed, and whoever killed them earns their xp; the player's death is noticed by end_turn.
+    Blows against the player are tallied under the attacker's name, which is the name of its monster template.
+    A spell hits as hard whoever casts it, so the caster's strength doesn't count, and its wounds are tallied under the spell's name.
+    Fighting is loud: every blow makes a noise where the attacker stands, so a shot gives away the shooter.
+ */
+#[system(for_each)]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Name)]
+#[read_component(Attributes)]
+#[read_component(XpReward)]
+#[write_component(Health)]
+#[allow(clippy::too_many_arguments, clippy::trivially_copy_pass_by_ref)]
+pub fn combat(
+    entity: &Entity,
+    attack: &WantsToAttack,
+    #[resource] map: &Map,
+    #[resource] index: &mut SpatialIndex,
+    #[resource] stats: &mut GameStats,
+    #[resource] noises: &mut Noises,
+    #[resource] spellbook: &Spellbook,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    commands.remove(*entity);
+
+    let Ok(attacker) = ecs.entry_ref(attack.attacker) else {
+        return;
+    };
+    let spell = attack.spell.and_then(|spell| spellbook.spells.get(spell));
+    let name = match spell {
+        Some(spell) => spell.name.clone(),
+        None => attacker.get_component::<Name>().map_or_else(|_| "Something".to_string(), |name| name.0.clone()),
+    };
+    let strength = match spell {
+        Some(_) => 0,
+        None => attacker.get_component::<Attributes>().map_or(0, |attributes| attributes.strength),
+    };
+    if let Ok(pos) = attacker.get_component::<Point>() {
+        noises.make(*pos, COMBAT_NOISE);
+    }
+
+    let Ok(mut victim) = ecs.entry_mut(attack.victim) else {
+        return;
+    };
+    let is_player = victim.get_component::<Player>().is_ok();
+    let toughness = victim.get_component::<Attributes>().map_or(0, |attributes| attributes.toughness);
+    let xp = victim.get_component::<XpReward>().map_or(0, |reward| reward.0);
+    let victim_pos = victim.get_component::<Point>().ok().copied();
+    let damage = i32::max(1, attack.damage + strength - toughness);
+    if let Ok(health) = victim.get_component_mut::<Health>() {
+        // A monster hit twice in the same turn only dies once.
+        let was_alive = health.current > 0;
+        health.current -= damage;
+        if is_player {
+            stats.player_hurt(&name, damage);
+        } else if health.current <= 0 && was_alive {
+            commands.remove(attack.victim);
+            if let Some(idx) = victim_pos.and_then(|pos| map.try_idx(pos)) {
+                index.remove(attack.victim, idx);
+            }
+            commands.push(((), EarnedXp { entity: attack.attacker, xp }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /*
+    Resolves every WantsToAttack message in the world, as the schedules do.
+     */
+    fn resolve(ecs: &mut World) -> Resources {
+        let mut resources = Resources::default();
+        let map = Map::new(5, 5);
+        let mut index = SpatialIndex::default();
+        index.clear(&map);
+        for (entity, pos) in <(Entity, &Point)>::query().iter(ecs) {
+            index.add(*entity, map.map_idx(pos.x, pos.y), true);
+        }
+        resources.insert(map);
+        resources.insert(index);
+        resources.insert(GameStats::default());
+        resources.insert(Noises::default());
+        resources.insert(Spellbook::default());
+        Schedule::builder().add_system(combat_system()).build().execute(ecs, &mut resources);
+        resources
+    }
+
+    // None once the entity is gone.
+    fn health(ecs: &World, entity: Entity) -> Option<i32> {
+        ecs.entry_ref(entity).ok().and_then(|entry| entry.get_component::<Health>().ok().map(|health| health.current))
+    }
+
+    fn attack(ecs: &mut World, attacker: Entity, victim: Entity, damage: i32) {
+        ecs.push(((), WantsToAttack { attacker, victim, damage, spell: None }));
+    }
+
+    fn spawn(ecs: &mut World, name: &str, hp: i32, pos: Point) -> Entity {
+        ecs.push((Enemy, Name(name.to_string()), pos, Health { current: hp, max: hp }))
+    }
+
+    #[test]
+    fn a_blow_takes_its_damage_from_the_victim() {
+        let mut ecs = World::default();
+        let ogre = spawn(&mut ecs, "Ogre", 4, Point::new(1, 1));
+        let ettin = spawn(&mut ecs, "Ettin", 10, Point::new(2, 1));
+        attack(&mut ecs, ogre, ettin, 2);
+        resolve(&mut ecs);
+        assert_eq!(health(&ecs, ettin), Some(8));
+        assert_eq!(<&WantsToAttack>::query().iter(&ecs).count(), 0);
+    }
+
+    #[test]
+    fn monsters_that_run_out_of_health_are_removed_but_the_player_is_left_to_end_turn() {
+        let mut ecs = World::default();
+        let ogre = spawn(&mut ecs, "Ogre", 4, Point::new(1, 1));
+        let goblin = spawn(&mut ecs, "Goblin", 1, Point::new(2, 1));
+        let player = ecs.push((Player { map_level: 0 }, Name("Player".to_string()), Point::new(1, 2), Health { current: 1, max: 20 }));
+        attack(&mut ecs, ogre, goblin, 2);
+        attack(&mut ecs, ogre, player, 2);
+        resolve(&mut ecs);
+        assert_eq!(health(&ecs, goblin), None);
+        assert_eq!(health(&ecs, player), Some(-1));
+    }
+
+    #[test]
+    fn blows_against_the_player_are_tallied_under_the_attackers_name() {
+        let mut ecs = World::default();
+        let ogre = spawn(&mut ecs, "Ogre", 4, Point::new(1, 1));
+        let player = ecs.push((Player { map_level: 0 }, Point::new(1, 2), Health { current: 20, max: 20 }));
+        attack(&mut ecs, ogre, player, 2);
+        attack(&mut ecs, ogre, player, 2);
+        let resources = resolve(&mut ecs);
+        assert_eq!(resources.get::<GameStats>().unwrap().damage_taken.get("Ogre"), Some(&4));
+    }
+
+    #[test]
+    fn toughness_softens_a_blow_but_never_below_one() {
+        let mut ecs = World::default();
+        let ogre = spawn(&mut ecs, "Ogre", 4, Point::new(1, 1));
+        let ettin = spawn(&mut ecs, "Ettin", 10, Point::new(2, 1));
+        ecs.entry(ettin).unwrap().add_component(Attributes { strength: 0, agility: 0, toughness: 5 });
+        attack(&mut ecs, ogre, ettin, 2);
+        resolve(&mut ecs);
+        assert_eq!(health(&ecs, ettin), Some(9));
+    }
+
+    #[test]
+    fn a_monster_killed_twice_in_one_turn_is_only_worth_its_xp_once() {
+        let mut ecs = World::default();
+        let player = ecs.push((Player { map_level: 0 }, Point::new(1, 2), Health { current: 20, max: 20 }));
+        let goblin = spawn(&mut ecs, "Goblin", 1, Point::new(2, 1));
+        ecs.entry(goblin).unwrap().add_component(XpReward(3));
+        attack(&mut ecs, player, goblin, 2);
+        attack(&mut ecs, player, goblin, 2);
+        resolve(&mut ecs);
+        let earned: Vec<i32> = <&EarnedXp>::query().iter(&ecs).map(|earned| earned.xp).collect();
+        assert_eq!(earned, vec![3]);
+    }
+
+    #[test]
+    fn a_killed_monster_leaves_the_spatial_index() {
+        let mut ecs = World::default();
+        let ogre = spawn(&mut ecs, "Ogre", 4, Point::new(1, 1));
+        let goblin = spawn(&mut ecs, "Goblin", 1, Point::new(2, 1));
+        attack(&mut ecs, ogre, goblin, 2);
+        let resources = resolve(&mut ecs);
+        let (map, index) = (resources.get::<Map>().unwrap(), resources.get::<SpatialIndex>().unwrap());
+        assert!(index.entities_at(map.map_idx(2, 1)).is_empty());
+        assert!(!index.is_blocked(map.map_idx(2, 1)));
+    }
+
+    #[test]
+    fn a_spell_hits_as_hard_whoever_casts_it_and_its_wounds_go_under_its_name() {
+        let mut ecs = World::default();
+        let player = ecs.push((Player { map_level: 0 }, Point::new(1, 2), Health { current: 20, max: 20 }));
+        ecs.entry(player).unwrap().add_component(Attributes { strength: 3, agility: 0, toughness: 0 });
+        let ettin = spawn(&mut ecs, "Ettin", 10, Point::new(2, 1));
+        ecs.push(((), WantsToAttack { attacker: player, victim: ettin, damage: 4, spell: Some(1) }));
+        ecs.push(((), WantsToAttack { attacker: player, victim: player, damage: 4, spell: Some(1) }));
+        let resources = resolve(&mut ecs);
+        assert_eq!(health(&ecs, ettin), Some(6));
+        let fireball = &resources.get::<Spellbook>().unwrap().spells[1].name;
+        assert_eq!(resources.get::<GameStats>().unwrap().damage_taken.get(fireball), Some(&4));
+    }
+}
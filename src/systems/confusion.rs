@@ -0,0 +1,22 @@
+use crate::prelude::*;
+
+/*
+    Runs once for every WantsToMove message, before the movement system sees it.
+    A confused mover doesn't go where it meant to, but one step in any of the eight directions, picked at random.
+    This works the same for the player and the monsters.
+ */
+#[system(for_each)]
+#[read_component(Point)]
+#[read_component(Confused)]
+pub fn confusion(want_move: &mut WantsToMove, #[resource] rng: &mut RandomNumberGenerator, ecs: &SubWorld) {
+    let Ok(mover) = ecs.entry_ref(want_move.entity) else {
+        return;
+    };
+    let (Ok(pos), Ok(_)) = (mover.get_component::<Point>(), mover.get_component::<Confused>()) else {
+        return;
+    };
+
+    let directions = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+    let (dx, dy) = directions[rng.range(0, directions.len())];
+    want_move.destination = *pos + Point::new(dx, dy);
+}
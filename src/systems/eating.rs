@@ -0,0 +1,49 @@
+use crate::prelude::*;
+
+/*
+    Runs once for every WantsToEat message: the food is eaten up, and the eater is that much less hungry,
+    up to being full. Food the player eats counts as an item used.
+ */
+#[system(for_each)]
+#[read_component(Point)]
+#[read_component(Food)]
+#[read_component(Name)]
+#[read_component(Player)]
+#[write_component(Hunger)]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub fn eating(
+    entity: &Entity,
+    eat: &WantsToEat,
+    #[resource] map: &Map,
+    #[resource] index: &mut SpatialIndex,
+    #[resource] stats: &mut GameStats,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    commands.remove(*entity);
+
+    let Ok(food) = ecs.entry_ref(eat.food) else {
+        return;
+    };
+    let Ok(nutrition) = food.get_component::<Food>().map(|food| food.nutrition) else {
+        return;
+    };
+    let name = food.get_component::<Name>().map_or_else(|_| "Food".to_string(), |name| name.0.clone());
+    let food_idx = food.get_component::<Point>().ok().and_then(|pos| map.try_idx(*pos));
+
+    let Ok(mut eater) = ecs.entry_mut(eat.eater) else {
+        return;
+    };
+    let is_player = eater.get_component::<Player>().is_ok();
+    let Ok(hunger) = eater.get_component_mut::<Hunger>() else {
+        return;
+    };
+    hunger.satiety = i32::min(hunger.max, hunger.satiety + nutrition);
+    commands.remove(eat.food);
+    if let Some(idx) = food_idx {
+        index.remove(eat.food, idx);
+    }
+    if is_player {
+        *stats.items_used.entry(name).or_default() += 1;
+    }
+}
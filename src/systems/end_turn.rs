@@ -0,0 +1,40 @@
+use crate::prelude::*;
+
+/*
+    The last system of the player, monster and new turn schedules decides who acts next, unless the player has died,
+    or something else already took the game out of the turn cycle. The player's move is paid for here.
+    The player keeps the turn as long as they have the energy for another move; the monsters then keep it
+    until none of them has the energy left to act, and a new turn begins.
+    The player's turn counts towards the game's statistics even if it took them to the next level.
+ */
+#[system]
+#[read_component(Health)]
+#[read_component(Player)]
+#[write_component(Initiative)]
+pub fn end_turn(ecs: &mut SubWorld, #[resource] turn_state: &mut TurnState, #[resource] stats: &mut GameStats) {
+    if matches!(turn_state, TurnState::PlayerTurn | TurnState::NextLevel) {
+        stats.turns += 1;
+    }
+
+    let mut player_hp = <&Health>::query().filter(component::<Player>());
+    if player_hp.iter(ecs).any(|hp| hp.current < 1) {
+        *turn_state = TurnState::GameOver;
+        stats.player_died();
+        return;
+    }
+
+    let mut player = <&mut Initiative>::query().filter(component::<Player>());
+    if *turn_state == TurnState::PlayerTurn {
+        player.for_each_mut(ecs, |initiative| initiative.energy -= ACTION_COST);
+    }
+    let player_ready = player.iter_mut(ecs).any(|initiative| initiative.is_ready());
+    let mut monsters = <&Initiative>::query().filter(!component::<Player>());
+    let monsters_ready = monsters.iter(ecs).any(|initiative| initiative.is_ready());
+
+    *turn_state = match turn_state {
+        TurnState::AwaitingInput | TurnState::NextLevel | TurnState::GameOver => return,
+        TurnState::PlayerTurn | TurnState::NewTurn if player_ready => TurnState::AwaitingInput,
+        _ if monsters_ready => TurnState::MonsterTurn,
+        _ => TurnState::NewTurn,
+    };
+}
@@ -4,11 +4,20 @@ use crate::prelude::*;
     The system requests read-only access to Point and Render components and read-only access to the Camera resource.
     The camera calculates the offset to apply to our entity’s screen position, just like we did before.
     The Point component tells us where the entity is, and the Render component describes its appearance.
+    Only entities the player can currently see, and knows about, are drawn, tinted by the light they stand in.
+    Anything standing in the dark can't be seen, even in line of sight.
  */
 #[system]
 #[read_component(Point)]
 #[read_component(Render)]
-pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera){
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+#[read_component(Hidden)]
+pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera, #[resource] map: &Map, #[resource] lighting: &Lighting){
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let Some(player_fov) = fov.iter(ecs).next() else {
+        return;
+    };
     let mut draw_batch = DrawBatch::new();
     draw_batch.target(1);
     let offset = Point::new(camera.left_x, camera.top_y);
@@ -20,7 +29,9 @@ pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera){
     // This query looks for entities that contain both a Point and a Render component,
     //  returning only entities that have both.
     <(&Point, &Render)>::query()
+        .filter(!component::<Hidden>())
         .iter(ecs)
+        .filter(|(pos, _)| player_fov.visible_tiles.contains(pos) && lighting.is_lit(map, **pos))
         //This uses for_each() that works the same on a query as it does on a vector.
         // Each call receives the query’s components in a tuple. Destructure these to use the components by name.
         .for_each(|(pos, render)| {
@@ -28,7 +39,7 @@ pub fn entity_render(ecs: &SubWorld, #[resource] camera: &Camera){
             //This sets the screen character at the position in pos to the glyph and color specified in the Render component
             draw_batch.set(
                 *pos -offset,
-                render.color,
+                ColorPair::new(lighting.tint(map, *pos, render.color.fg), render.color.bg),
                 render.glyph,
             );
         });
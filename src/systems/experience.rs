@@ -0,0 +1,32 @@
+use crate::prelude::*;
+
+/*
+    Runs once for every EarnedXp message. Only the player keeps track of xp; anything else that earns some forgets it.
+    Every level the xp takes the player up raises their maximum health, which resting heals them up to,
+    and gives them a perk to pick from the level-up menu.
+ */
+#[system(for_each)]
+#[write_component(Experience)]
+#[write_component(Health)]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub fn experience(entity: &Entity, earned: &EarnedXp, ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+    commands.remove(*entity);
+
+    let Ok(mut entry) = ecs.entry_mut(earned.entity) else {
+        return;
+    };
+    let Ok(experience) = entry.get_component_mut::<Experience>() else {
+        return;
+    };
+    experience.xp += earned.xp;
+    let mut levels = 0;
+    while experience.xp >= xp_to_leave_level(experience.level) {
+        experience.level += 1;
+        experience.perks_to_pick += 1;
+        levels += 1;
+    }
+
+    if let Ok(health) = entry.get_component_mut::<Health>() {
+        health.max += levels * HEALTH_PER_LEVEL;
+    }
+}
@@ -0,0 +1,32 @@
+use crate::prelude::*;
+
+/*
+    Recalculates the visible tiles of every entity whose field of view went stale.
+    field_of_view_set() walks outwards from the entity's position and stops at anything the map reports as opaque.
+    Whatever the player sees is also marked as revealed on the map, so it stays drawn after the player walks away.
+    Tiles in line of sight but too dark to make out aren't revealed. The visible set itself ignores the light,
+    since lights can move without the viewer moving; ask Lighting::is_lit() as well before treating a tile as seen.
+ */
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[write_component(FieldOfView)]
+pub fn fov(ecs: &mut SubWorld, #[resource] map: &mut Map, #[resource] lighting: &Lighting) {
+    let mut views = <(&Point, &mut FieldOfView)>::query();
+    views
+        .iter_mut(ecs)
+        .filter(|(_, fov)| fov.is_dirty)
+        .for_each(|(pos, fov)| {
+            fov.visible_tiles = field_of_view_set(*pos, fov.radius, map);
+            fov.is_dirty = false;
+        });
+
+    let mut player_fov = <&FieldOfView>::query().filter(component::<Player>());
+    player_fov.iter(ecs).for_each(|fov| {
+        fov.visible_tiles.iter().for_each(|pos| {
+            if let Some(idx) = map.try_idx(*pos).filter(|_| lighting.is_lit(map, *pos)) {
+                map.revealed_tiles[idx] = true;
+            }
+        });
+    });
+}
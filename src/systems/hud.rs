@@ -0,0 +1,50 @@
+use crate::prelude::*;
+
+/*
+    A status line along the bottom of the HUD layer: the player's health and mana, their level and the xp they need
+    to get to the next one, how hungry they are once they are no longer well fed,
+    and every status effect on them with the turns it has left.
+ */
+#[system]
+#[read_component(Player)]
+#[read_component(Health)]
+#[read_component(Mana)]
+#[read_component(Experience)]
+#[read_component(Hunger)]
+#[read_component(Poisoned)]
+#[read_component(Confused)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+pub fn hud(ecs: &SubWorld) {
+    let mut players = <Entity>::query().filter(component::<Player>());
+    let Some(player) = players.iter(ecs).next().and_then(|player| ecs.entry_ref(*player).ok()) else {
+        return;
+    };
+
+    let mut status = Vec::new();
+    if let Ok(health) = player.get_component::<Health>() {
+        status.push(format!("Health: {} / {}", health.current, health.max));
+    }
+    if let Ok(mana) = player.get_component::<Mana>() {
+        status.push(format!("Mana: {} / {}", mana.current, mana.max));
+    }
+    if let Ok(experience) = player.get_component::<Experience>() {
+        status.push(format!(
+            "Level {}   XP: {} / {}",
+            experience.level,
+            experience.xp,
+            xp_to_leave_level(experience.level)
+        ));
+    }
+    if let Some(hunger) = player.get_component::<Hunger>().ok().map(|hunger| hunger.state()) {
+        if hunger != HungerState::WellFed {
+            status.push(hunger.name().to_string());
+        }
+    }
+    status.extend(describe_status_effects(&player));
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.print_color(Point::new(1, SCREEN_HEIGHT * 2 - 2), status.join("   "), ColorPair::new(WHITE, BLACK));
+    draw_batch.submit(10200).expect("Batch error");
+}
@@ -0,0 +1,23 @@
+use crate::prelude::*;
+
+/*
+    Runs at the start of every turn: everyone who gets hungry gets a little hungrier.
+    Starving takes a hit point every turn. The player's wounds are tallied as Starvation; their death is noticed by end_turn.
+ */
+#[system]
+#[read_component(Player)]
+#[write_component(Hunger)]
+#[write_component(Health)]
+pub fn hunger(ecs: &mut SubWorld, #[resource] stats: &mut GameStats) {
+    let mut hungry = <(&mut Hunger, &mut Health, Option<&Player>)>::query();
+    hungry.for_each_mut(ecs, |(hunger, health, player)| {
+        hunger.satiety = i32::max(0, hunger.satiety - 1);
+        if hunger.state() != HungerState::Starving {
+            return;
+        }
+        health.current -= 1;
+        if player.is_some() {
+            stats.player_hurt("Starvation", 1);
+        }
+    });
+}
@@ -0,0 +1,16 @@
+use crate::prelude::*;
+
+/*
+    Starts a new turn: every creature gains its speed in energy. Haste doubles the gain and slow halves it.
+ */
+#[system(for_each)]
+pub fn initiative(initiative: &mut Initiative, hasted: Option<&Hasted>, slowed: Option<&Slowed>) {
+    let mut gain = initiative.speed;
+    if hasted.is_some() {
+        gain *= 2;
+    }
+    if slowed.is_some() {
+        gain /= 2;
+    }
+    initiative.energy += gain;
+}
@@ -0,0 +1,50 @@
+use crate::prelude::*;
+
+/*
+    While the player has a perk to pick, the level-up menu takes the keys instead of the game:
+    north and south move through the perks and Wait picks one. Picking is free, it doesn't use up a turn.
+    It runs before player_input, and takes the action away from it.
+ */
+#[system]
+#[read_component(Player)]
+#[write_component(Experience)]
+#[write_component(Attributes)]
+#[write_component(Mana)]
+pub fn level_up(ecs: &mut SubWorld, #[resource] action: &mut Option<Action>, #[resource] menu: &mut LevelUpMenu) {
+    let mut players = <Entity>::query().filter(component::<Player>());
+    let Some(player) = players.iter(ecs).next().copied() else {
+        return;
+    };
+    let mut entry = ecs.entry_mut(player).unwrap();
+    if !entry.get_component::<Experience>().is_ok_and(|experience| experience.perks_to_pick > 0) {
+        return;
+    }
+    let Some(chosen) = action.take() else {
+        return;
+    };
+
+    let count = PERKS.len();
+    match chosen {
+        Action::MoveNorth => menu.selected = (menu.selected + count - 1) % count,
+        Action::MoveSouth => menu.selected = (menu.selected + 1) % count,
+        Action::Wait => {
+            let perk = PERKS[menu.selected];
+            if let Ok(attributes) = entry.get_component_mut::<Attributes>() {
+                match perk {
+                    Perk::Strength => attributes.strength += 1,
+                    Perk::Agility => attributes.agility += 1,
+                    Perk::Toughness => attributes.toughness += 1,
+                    Perk::ArcanePower => {}
+                }
+            }
+            if perk == Perk::ArcanePower {
+                if let Ok(mana) = entry.get_component_mut::<Mana>() {
+                    mana.max += 3;
+                    mana.current += 3;
+                }
+            }
+            entry.get_component_mut::<Experience>().unwrap().perks_to_pick -= 1;
+        }
+        _ => {}
+    }
+}
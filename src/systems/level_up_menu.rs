@@ -0,0 +1,40 @@
+use crate::prelude::*;
+
+/*
+    Draws the level-up menu on the HUD layer while the player has a perk to pick: their new level, then every perk.
+    The perk the menu points at is highlighted.
+ */
+#[system]
+#[read_component(Player)]
+#[read_component(Experience)]
+pub fn level_up_menu(ecs: &SubWorld, #[resource] menu: &LevelUpMenu, #[resource] keymap: &Keymap) {
+    let mut experience = <&Experience>::query().filter(component::<Player>());
+    let Some(experience) = experience.iter(ecs).next().filter(|experience| experience.perks_to_pick > 0) else {
+        return;
+    };
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.print_color(
+        Point::new(4, 4),
+        format!("You have reached level {}. Pick a perk:", experience.level - experience.perks_to_pick + 1),
+        ColorPair::new(WHITE, BLACK),
+    );
+    for (i, perk) in PERKS.iter().enumerate() {
+        let (color, pointer) = if i == menu.selected { (YELLOW, '>') } else { (WHITE, ' ') };
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let y = 6 + i as i32;
+        draw_batch.print_color(Point::new(4, y), format!("{pointer} {}", perk.describe()), ColorPair::new(color, BLACK));
+    }
+
+    let key = |action| keymap.key_for(action).map_or_else(|| "(unbound)".to_string(), |key| format!("{key:?}"));
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let y = 7 + PERKS.len() as i32;
+    draw_batch.print_color(
+        Point::new(4, y),
+        format!("{}/{} choose, {} picks.", key(Action::MoveNorth), key(Action::MoveSouth), key(Action::Wait)),
+        ColorPair::new(GRAY, BLACK),
+    );
+
+    draw_batch.submit(10000).expect("Batch error");
+}
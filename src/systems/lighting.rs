@@ -0,0 +1,52 @@
+use crate::prelude::*;
+
+/*
+    Recalculates the light on every tile from scratch.
+    Each light shines on the tiles it has a line of sight to, using the same field_of_view_set() as vision,
+    and fades out towards the edge of its radius. Overlapping lights add up.
+ */
+#[system]
+#[read_component(Point)]
+#[read_component(LightSource)]
+#[allow(clippy::cast_precision_loss)]
+pub fn lighting(ecs: &SubWorld, #[resource] map: &Map, #[resource] lighting: &mut Lighting) {
+    lighting.clear(map);
+
+    <(&Point, &LightSource)>::query().iter(ecs).for_each(|(pos, light)| {
+        let reach = light.radius as f32 + 1.0;
+        for tile in field_of_view_set(*pos, light.radius, map) {
+            if let Some(idx) = map.try_idx(tile) {
+                let distance = DistanceAlg::Pythagoras.distance2d(*pos, tile);
+                let strength = (1.0 - distance / reach).max(0.0);
+                lighting.add_light(idx, light.color, strength);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn light_fades_with_distance_and_stops_at_walls() {
+        let mut map = Map::new(9, 3);
+        for y in 0 .. 3 {
+            let wall = map.map_idx(4, y);
+            map.tiles[wall] = TileType::Wall;
+        }
+        let mut ecs = World::default();
+        ecs.push((Point::new(1, 1), LightSource { radius: 6, color: RGB::named(WHITE) }));
+        let mut resources = Resources::default();
+        resources.insert(map);
+        resources.insert(Lighting::default());
+
+        Schedule::builder().add_system(lighting_system()).build().execute(&mut ecs, &mut resources);
+
+        let map = resources.get::<Map>().unwrap();
+        let lighting = resources.get::<Lighting>().unwrap();
+        let intensity = |x| lighting.intensity(&map, Point::new(x, 1));
+        assert!(intensity(1) > intensity(2) && intensity(2) > intensity(3));
+        assert!(!lighting.is_lit(&map, Point::new(5, 1)));
+    }
+}
@@ -7,10 +7,25 @@ use crate::prelude::*;
      We’ve added draw commands to a batch with the same commands as immediate mode, but call the batch rather than the context.
      Submitting the batch adds it to the global command list. It accepts a single integer parameter, serving as sort order.
         Zero renders first, ensuring that our map is drawn at the beginning of the render cycle.
+    Only tiles the player has revealed are drawn. Those in view are bright, remembered ones are dimmed.
+    The level's theme decides what each tile looks like, and the light reaching a tile tints it.
+    Tiles in line of sight that are too dark to see are drawn as remembered.
  */
 
 #[system]
-pub fn map_render(#[resource] map: &Map, #[resource] camera: &Camera) {
+#[read_component(FieldOfView)]
+#[read_component(Player)]
+pub fn map_render(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] camera: &Camera,
+    #[resource] lighting: &Lighting,
+) {
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let Some(player_fov) = fov.iter(ecs).next() else {
+        return;
+    };
+    let theme = map.theme.theme();
     let mut draw_batch = DrawBatch::new();
 
     draw_batch.target(0);
@@ -20,18 +35,16 @@ pub fn map_render(#[resource] map: &Map, #[resource] camera: &Camera) {
             let pt = Point::new(x,y);
             let offset = Point::new(camera.left_x, camera.top_y);
 
-            if map.in_bounds(pt) {
-                let idx = map_idx(x,y);
-                let glyph = match map.tiles[idx] {
-                    TileType::Floor => to_cp437('.'),
-                    TileType::Wall => to_cp437('#'),
+            if let Some(idx) = map.try_idx(pt).filter(|idx| map.revealed_tiles[*idx]) {
+                let (glyph, color) = theme.tile_to_render(map.tiles[idx]);
+                let color = if player_fov.visible_tiles.contains(&pt) && lighting.is_lit(map, pt) {
+                    ColorPair::new(lighting.tint(map, pt, color.fg), color.bg)
+                } else {
+                    ColorPair::new(DARK_GRAY, color.bg)
                 };
                 draw_batch.set(
                     pt - offset,
-                    ColorPair::new(
-                        WHITE,
-                        BLACK
-                    ),
+                    color,
                     glyph
                 );
             }
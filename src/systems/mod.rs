@@ -1,19 +1,121 @@
 mod player_input;
 mod map_render;
 mod entity_render;
-mod collisions;
+mod fov;
+mod tooltips;
+mod movement;
+mod monster_ai;
+mod alertness;
+mod end_turn;
+mod traps;
+mod combat;
+mod ranged;
+mod targeting;
+mod spells;
+mod spell_menu;
+mod confusion;
+mod status_effects;
+mod hud;
+mod initiative;
+mod experience;
+mod level_up;
+mod level_up_menu;
+mod hunger;
+mod eating;
+mod resting;
+mod perception;
+mod spatial_index;
+mod lighting;
 
 use crate::prelude::*;
 
 /*
-This function creates a Legion Schedule (an execution plan for our systems).
-t follows the builder pattern: Schedule::builder starts the system building process, and build() finishes it.
+These functions create Legion Schedules (an execution plan for our systems), one for each TurnState.
+They follow the builder pattern: Schedule::builder starts the system building process, and build() finishes it.
+flush() applies the command buffers of the systems before it, so later systems see their changes.
  */
-pub fn build_scheduler() -> Schedule {
+pub fn build_input_scheduler() -> Schedule {
     Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(level_up::level_up_system())
         .add_system(player_input::player_input_system())
-        .add_system(collisions::collisions_system())
+        .add_system(lighting::lighting_system())
+        .add_system(fov::fov_system())
+        .flush()
         .add_system(map_render::map_render_system())
         .add_system(entity_render::entity_render_system())
+        .add_system(tooltips::tooltips_system())
+        .add_system(targeting::targeting_system())
+        .add_system(spell_menu::spell_menu_system())
+        .add_system(level_up_menu::level_up_menu_system())
+        .add_system(hud::hud_system())
         .build()
-}
\ No newline at end of file
+}
+
+pub fn build_player_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .flush()
+        .add_system(confusion::confusion_system())
+        .add_system(movement::movement_system())
+        .add_system(ranged::ranged_system())
+        .add_system(spells::spells_system())
+        .add_system(eating::eating_system())
+        .add_system(resting::resting_system())
+        .flush()
+        .add_system(traps::traps_system())
+        .add_system(combat::combat_system())
+        .flush()
+        .add_system(perception::perception_system())
+        .flush()
+        .add_system(experience::experience_system())
+        .flush()
+        .add_system(lighting::lighting_system())
+        .add_system(fov::fov_system())
+        .flush()
+        .add_system(map_render::map_render_system())
+        .add_system(entity_render::entity_render_system())
+        .add_system(end_turn::end_turn_system())
+        .build()
+}
+
+pub fn build_monster_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(spatial_index::spatial_index_system())
+        .add_system(alertness::alertness_system())
+        .add_system(monster_ai::monster_ai_system())
+        .flush()
+        .add_system(confusion::confusion_system())
+        .add_system(movement::movement_system())
+        .flush()
+        .add_system(traps::traps_system())
+        .add_system(combat::combat_system())
+        .flush()
+        .add_system(experience::experience_system())
+        .flush()
+        .add_system(lighting::lighting_system())
+        .add_system(fov::fov_system())
+        .flush()
+        .add_system(map_render::map_render_system())
+        .add_system(entity_render::entity_render_system())
+        .add_system(end_turn::end_turn_system())
+        .build()
+}
+
+/*
+Runs between the monsters' last move and the player's next one.
+ */
+pub fn build_new_turn_scheduler() -> Schedule {
+    Schedule::builder()
+        .add_system(status_effects::status_effects_system())
+        .add_system(hunger::hunger_system())
+        .add_system(initiative::initiative_system())
+        .flush()
+        .add_system(lighting::lighting_system())
+        .add_system(fov::fov_system())
+        .flush()
+        .add_system(map_render::map_render_system())
+        .add_system(entity_render::entity_render_system())
+        .add_system(end_turn::end_turn_system())
+        .build()
+}
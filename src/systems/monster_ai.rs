@@ -0,0 +1,85 @@
+use crate::prelude::*;
+
+/*
+    Every monster with the energy for an action spends it, according to what it knows about the player (see alertness).
+    A chasing or searching monster takes the next step of the shortest path to where it saw or heard the player,
+    taking its way with doors and water into account (see PathingMap); once it is there, or can't find a way,
+    it looks around by wandering. Every action spent searching brings the monster
+    closer to giving up, after which it goes back to wandering.
+    A wandering monster picks one of the four directions at random and asks to move there. They aren't completely
+    mindless: a monster that picks a hazard stays put instead.
+    Monsters still busy with a slow action, or asleep, spend the action doing nothing.
+    Fast monsters may still have energy left afterwards; end_turn runs the monster schedule again for them.
+ */
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Busy)]
+#[read_component(OpensDoors)]
+#[read_component(Swimmer)]
+#[write_component(Alertness)]
+#[write_component(Initiative)]
+pub fn monster_ai(
+    ecs: &mut SubWorld,
+    #[resource] map: &Map,
+    #[resource] index: &SpatialIndex,
+    #[resource] rng: &mut RandomNumberGenerator,
+    commands: &mut CommandBuffer,
+) {
+    let mut actors = <(
+        Entity,
+        &Point,
+        &mut Initiative,
+        &mut Alertness,
+        Option<&Busy>,
+        Option<&OpensDoors>,
+        Option<&Swimmer>,
+    )>::query()
+    .filter(!component::<Player>());
+    actors.for_each_mut(ecs, |(entity, pos, initiative, alertness, busy, opens_doors, swimmer)| {
+        if !initiative.is_ready() {
+            return;
+        }
+        initiative.energy -= ACTION_COST;
+
+        if let Some(busy) = busy {
+            if busy.turns > 1 {
+                commands.add_component(*entity, Busy { turns: busy.turns - 1 });
+            } else {
+                commands.remove_component::<Busy>(*entity);
+            }
+            return;
+        }
+
+        let pathing = PathingMap { map, index, opens_doors: opens_doors.is_some(), can_swim: swimmer.is_some() };
+        let destination = match *alertness {
+            Alertness::Asleep => None,
+            Alertness::Wandering => wander(map, rng, *pos),
+            Alertness::Chasing { target } => pathing.step_towards(*pos, target).or_else(|| wander(map, rng, *pos)),
+            Alertness::Searching { target, turns } => {
+                *alertness = if turns > 1 {
+                    Alertness::Searching { target, turns: turns - 1 }
+                } else {
+                    Alertness::Wandering
+                };
+                pathing.step_towards(*pos, target).or_else(|| wander(map, rng, *pos))
+            }
+        };
+        if let Some(destination) = destination {
+            commands.push(((), WantsToMove { entity: *entity, destination }));
+        }
+    });
+}
+
+/*
+    One of the four neighbouring tiles at random, unless it is a hazard.
+ */
+fn wander(map: &Map, rng: &mut RandomNumberGenerator, pos: Point) -> Option<Point> {
+    let destination = match rng.range(0, 4) {
+        0 => Point::new(-1, 0),
+        1 => Point::new(1, 0),
+        2 => Point::new(0, -1),
+        _ => Point::new(0, 1),
+    } + pos;
+    Some(destination).filter(|destination| !map.tile(*destination).is_some_and(TileType::is_hazard))
+}
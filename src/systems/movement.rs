@@ -0,0 +1,141 @@
+use crate::prelude::*;
+
+/*
+    Runs once for every WantsToMove message.
+    The move only happens if the destination can be entered and, when the settings ask for it, the step doesn't cut a corner.
+    Bumping into a closed door opens it instead, if the mover knows how; the mover stays where it is. Doors creak,
+    so opening one makes a noise.
+    Either way the message is deleted, so a blocked move or opening a door still uses up the mover's turn.
+    Stepping onto a tile also applies its rules: slow tiles keep the mover busy, lava burns, and chasms drop it a level.
+    Agile movers get through slow tiles sooner, and tough ones are burned less, though lava always does some damage.
+    Tiles with something standing on them can't be entered. Walking into the other side is how the player and the monsters
+    fight: the player attacks the enemies they walk into, and monsters the player (see combat). Either way the mover stays put.
+    The spatial index is updated as each move happens, so two movers can't squeeze onto the same free tile.
+ */
+#[system(for_each)]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(OpensDoors)]
+#[read_component(Swimmer)]
+#[read_component(BlocksTile)]
+#[read_component(Enemy)]
+#[read_component(Damage)]
+#[read_component(Confused)]
+#[read_component(Attributes)]
+#[write_component(Health)]
+#[allow(clippy::too_many_arguments, clippy::trivially_copy_pass_by_ref)]
+pub fn movement(
+    entity: &Entity,
+    want_move: &WantsToMove,
+    #[resource] map: &mut Map,
+    #[resource] settings: &Settings,
+    #[resource] camera: &mut Camera,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] index: &mut SpatialIndex,
+    #[resource] stats: &mut GameStats,
+    #[resource] noises: &mut Noises,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    commands.remove(*entity);
+
+    let Ok(entry) = ecs.entry_ref(want_move.entity) else {
+        return;
+    };
+    let from = *entry.get_component::<Point>().unwrap();
+    let is_player = entry.get_component::<Player>().is_ok();
+    let can_swim = entry.get_component::<Swimmer>().is_ok();
+    let opens_doors = entry.get_component::<OpensDoors>().is_ok();
+    // Confused monsters are too dazed to fight; they blunder into the player without hurting them.
+    let dazed = entry.get_component::<Enemy>().is_ok() && entry.get_component::<Confused>().is_ok();
+    let attack = entry.get_component::<Damage>().ok().filter(|_| !dazed).map(|damage| damage.0);
+    let fov = entry.get_component::<FieldOfView>().ok().map(FieldOfView::clone_dirty);
+    let attributes = entry.get_component::<Attributes>().ok().copied();
+    let (agility, toughness) = attributes.map_or((0, 0), |attributes| (attributes.agility, attributes.toughness));
+
+    if settings.block_diagonal_corners && map.cuts_corner(from, want_move.destination) {
+        return;
+    }
+
+    if map.is_closed_door(want_move.destination) {
+        if opens_doors {
+            map.open_door(want_move.destination);
+            noises.make(want_move.destination, DOOR_NOISE);
+            // Anyone who could see the door can now see through it.
+            <(Entity, &FieldOfView)>::query()
+                .iter(ecs)
+                .filter(|(_, fov)| fov.visible_tiles.contains(&want_move.destination))
+                .for_each(|(viewer, fov)| commands.add_component(*viewer, fov.clone_dirty()));
+        }
+        return;
+    }
+
+    let Some(cost) = map.move_cost(want_move.destination, can_swim) else {
+        return;
+    };
+    let tile = map.tile(want_move.destination).unwrap();
+    let from_idx = map.map_idx(from.x, from.y);
+    let to_idx = map.map_idx(want_move.destination.x, want_move.destination.y);
+
+    if index.is_blocked(to_idx) {
+        let opponent = index.entities_at(to_idx).iter().copied().find(|other| {
+            ecs.entry_ref(*other).is_ok_and(|other| {
+                if is_player {
+                    other.get_component::<Enemy>().is_ok()
+                } else {
+                    other.get_component::<Player>().is_ok()
+                }
+            })
+        });
+        if let (Some(victim), Some(damage)) = (opponent, attack) {
+            commands.push(((), WantsToAttack { attacker: want_move.entity, victim, damage, spell: None }));
+            return;
+        }
+        let blocked = index.entities_at(to_idx).iter().any(|other| {
+            ecs.entry_ref(*other).is_ok_and(|other| other.get_component::<BlocksTile>().is_ok())
+        });
+        if blocked {
+            return;
+        }
+    }
+    index.move_entity(want_move.entity, from_idx, to_idx);
+
+    commands.add_component(want_move.entity, want_move.destination);
+    commands.push(((), EnteredTile { entity: want_move.entity, destination: want_move.destination }));
+    if let Some(fov) = fov {
+        commands.add_component(want_move.entity, fov);
+    }
+    if is_player {
+        camera.on_player_move(want_move.destination, map);
+    }
+    if cost - agility > 1 {
+        commands.add_component(want_move.entity, Busy { turns: cost - agility - 1 });
+    }
+
+    let damage = tile.entry_damage();
+    if damage > 0 {
+        let damage = i32::max(1, damage - toughness);
+        if let Ok(health) = ecs.entry_mut(want_move.entity).unwrap().get_component_mut::<Health>() {
+            health.current -= damage;
+            if is_player {
+                stats.player_hurt("Lava", damage);
+            }
+            // The player's death is noticed by end_turn; monsters simply burn up.
+            if health.current <= 0 && !is_player {
+                commands.remove(want_move.entity);
+                index.remove(want_move.entity, to_idx);
+            }
+        }
+    }
+
+    if tile == TileType::Chasm {
+        if is_player {
+            *turn_state = TurnState::NextLevel;
+        } else {
+            // Monsters that fall are gone from this level.
+            commands.remove(want_move.entity);
+            index.remove(want_move.entity, to_idx);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::prelude::*;
+
+/*
+    Each turn the player has a one in three chance of noticing every hidden trap right next to them.
+ */
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+pub fn perception(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] index: &SpatialIndex,
+    #[resource] rng: &mut RandomNumberGenerator,
+    commands: &mut CommandBuffer,
+) {
+    let mut players = <&Point>::query().filter(component::<Player>());
+    let Some(player_pos) = players.iter(ecs).next() else {
+        return;
+    };
+
+    for y in player_pos.y - 1 ..= player_pos.y + 1 {
+        for x in player_pos.x - 1 ..= player_pos.x + 1 {
+            let Some(idx) = map.try_idx(Point::new(x, y)) else {
+                continue;
+            };
+            for entity in index.entities_at(idx) {
+                let hidden_trap = ecs.entry_ref(*entity).is_ok_and(|entry| {
+                    entry.get_component::<Trap>().is_ok() && entry.get_component::<Hidden>().is_ok()
+                });
+                if hidden_trap && rng.range(0, 3) == 0 {
+                    commands.remove_component::<Hidden>(*entity);
+                }
+            }
+        }
+    }
+}
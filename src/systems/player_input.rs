@@ -1,5 +1,8 @@
 use crate::prelude::*;
 
+// How far away a search finds hidden traps.
+const SEARCH_RADIUS: f32 = 2.0;
+
 /*
 This is our first nested module—the module is inside our systems module.
 The #[system] line annotates the player_input function with a procedural macro named system.
@@ -7,51 +10,242 @@ This macro transforms the function name into player_input_system
 and wraps it with all the extra code Legion requires to construct a system.
  */
 #[system]
-//write_component requests writable access to a component type,
-//We must request write access if we intend to change the contents of a component in your system.
-#[write_component(Point)]
 //read_component requests read-only access to a component type.
 //We must request read access to use the values stored in a component of this type, but we can’t make changes to the stored value.
+#[read_component(Point)]
 #[read_component(Player)]
+#[read_component(Enemy)]
+#[read_component(FieldOfView)]
+#[read_component(Trap)]
+#[read_component(Hidden)]
+#[read_component(RangedWeapon)]
+#[read_component(Food)]
+#[read_component(Mana)]
+//write_component requests writable access to a component type,
+//We must request write access if we intend to change the contents of a component in your system.
+#[write_component(Busy)]
+//Legion hands every resource to a system by reference, one parameter each, even small Copy ones like the action.
+//Clippy's advice about passing those by value, or about long parameter lists, can't be followed in a system.
+#[allow(clippy::too_many_arguments, clippy::ref_option, clippy::trivially_copy_pass_by_ref)]
 pub fn player_input(
     //A SubWorld is like a World but can only see the components we request.
     ecs: &mut SubWorld,
     //#[resource] requests access to types we stored in Legion’s Resource handler. It’s also a procedural macro.
+    #[resource] action: &Option<Action>,
+    #[resource] index: &SpatialIndex,
     #[resource] map: &Map,
-    #[resource] key: &Option<VirtualKeyCode>,
+    #[resource] lighting: &Lighting,
     #[resource] camera: &mut Camera,
+    #[resource] targeting: &mut Targeting,
+    #[resource] spellbook: &Spellbook,
+    #[resource] turn_state: &mut TurnState,
+    //Systems can also create entities. The command buffer applies them once the system is finished.
+    commands: &mut CommandBuffer,
 ){
-    if let Some(key) = key {
-        let delta = match key {
-            VirtualKeyCode::Left => Point::new(-1, 0),
-            VirtualKeyCode::Right => Point::new(1, 0),
-            VirtualKeyCode::Up => Point::new(0, -1),
-            VirtualKeyCode::Down => Point::new(0, 1),
-            _ => Point::new(0, 0),
+    //A player still busy with a slow action sits the turn out without waiting for a key.
+    let mut busy = <(Entity, &mut Busy)>::query().filter(component::<Player>());
+    if let Some((player, busy)) = busy.iter_mut(ecs).next() {
+        busy.turns -= 1;
+        if busy.turns < 1 {
+            commands.remove_component::<Busy>(*player);
+        }
+        *turn_state = TurnState::PlayerTurn;
+        return;
+    }
+
+    //Keys that aren't bound to any Action don't do anything.
+    let Some(action) = *action else {
+        return;
+    };
+
+    //We access components with a query
+    //Queries list one or more components and return references, mutable if we use &mut to each instance of that component type.
+    //Legion queries include a filter() function to further refine the set of components required for a query to match an entity.
+    //
+    //This line specifies that only entities with a Point component and a Player tag component should be included in the query.
+    let mut players = <(Entity, &Point)>::query().filter(component::<Player>());
+    let (player, player_pos) = players
+        .iter(ecs)
+        .map(|(entity, pos)| (*entity, *pos))
+        .next()
+        .unwrap();
+
+    //While the cast menu is open, the keys pick a spell from it. Picking one starts aiming it,
+    //except for spells the player can only cast on themselves, which take effect straight away.
+    if let Some(selected) = targeting.menu {
+        if let Some(spell) = choose_spell(ecs, spellbook, targeting, action, selected) {
+            if spellbook.spells[spell].range == 0 {
+                commands.push(((), WantsToCast { caster: player, spell, target: player_pos }));
+                *turn_state = TurnState::PlayerTurn;
+            } else {
+                targeting.target = Some(next_enemy(ecs, map, lighting, player_pos, None).unwrap_or(player_pos));
+                targeting.spell = Some(spell);
+            }
+        }
+        return;
+    }
+    if action == Action::Cast {
+        if !spellbook.spells.is_empty() && ecs.entry_ref(player).unwrap().get_component::<Mana>().is_ok() {
+            targeting.menu = Some(0);
+        }
+        return;
+    }
+
+    //While aiming, the movement keys move the target instead of the player.
+    if let Some(target) = targeting.target {
+        if action == Action::Fire {
+            match targeting.spell {
+                Some(spell) => commands.push(((), WantsToCast { caster: player, spell, target })),
+                None => commands.push(((), WantsToFire { shooter: player, target })),
+            };
+            *targeting = Targeting::default();
+            *turn_state = TurnState::PlayerTurn;
+        } else {
+            aim(ecs, map, lighting, targeting, action, target);
+        }
+        return;
+    }
+    if action == Action::Fire {
+        //Aiming is free; only the shot uses up the turn.
+        if ecs.entry_ref(player).unwrap().get_component::<RangedWeapon>().is_ok() {
+            targeting.target = Some(next_enemy(ecs, map, lighting, player_pos, None).unwrap_or(player_pos));
+        }
+        return;
+    }
+
+    //Free-look borrows the movement keys to pan the camera. Looking around is free, it never uses up a turn.
+    if action == Action::Look {
+        camera.free_look = !camera.free_look;
+        if !camera.free_look {
+            camera.look_at(player_pos, map);
+        }
+        return;
+    }
+    if camera.free_look {
+        if let Some(delta) = action.delta() {
+            camera.pan(delta, map);
+        }
+        return;
+    }
+
+    if let Some(delta) = action.delta() {
+        //Rather than moving the player here, we send a message and let the movement system decide whether the move is legal.
+        commands.push(((), WantsToMove { entity: player, destination: player_pos + delta }));
+    } else if action == Action::Wait {
+        if !enemy_in_sight(ecs, map, lighting) {
+            commands.push(((), WantsToRest { entity: player }));
+        }
+    } else if action == Action::Eat {
+        //Food is eaten where it lies.
+        let here = index.entities_at(map.map_idx(player_pos.x, player_pos.y));
+        let Some(food) = here.iter().copied().find(|entity| is_food(ecs, *entity)) else {
+            return;
         };
+        commands.push(((), WantsToEat { eater: player, food }));
+    } else if action == Action::Search {
+        //Searching always finds the hidden traps within a couple of tiles.
+        <(Entity, &Point)>::query()
+            .filter(component::<Trap>() & component::<Hidden>())
+            .iter(ecs)
+            .filter(|(_, pos)| DistanceAlg::Pythagoras.distance2d(**pos, player_pos) <= SEARCH_RADIUS)
+            .for_each(|(trap, _)| commands.remove_component::<Hidden>(*trap));
+    } else if action == Action::Descend && map.tile(player_pos) == Some(TileType::Exit) {
+        *turn_state = TurnState::NextLevel;
+        return;
+    } else {
+        //Actions without anything to act on don't use up the turn.
+        return;
+    }
+
+    *turn_state = TurnState::PlayerTurn;
+}
+
+/*
+Handles the keys pressed while the cast menu is open: north and south move through the list,
+Cast or Fire picks the spell, if the player has the mana for it, and Cancel closes the menu.
+Returns the spell picked, which closes the menu.
+ */
+fn choose_spell(ecs: &SubWorld, spellbook: &Spellbook, targeting: &mut Targeting, action: Action, selected: usize) -> Option<usize> {
+    let count = spellbook.spells.len();
+    match action {
+        Action::MoveNorth => targeting.menu = Some((selected + count - 1) % count),
+        Action::MoveSouth => targeting.menu = Some((selected + 1) % count),
+        Action::Cast | Action::Fire => {
+            let mut mana = <&Mana>::query().filter(component::<Player>());
+            if mana.iter(ecs).next().is_some_and(|mana| mana.current >= spellbook.spells[selected].mana) {
+                targeting.menu = None;
+                return Some(selected);
+            }
+        }
+        Action::Cancel => targeting.menu = None,
+        _ => {}
+    }
+    None
+}
+
+/*
+Handles the keys pressed while aiming: the movement keys move the target one tile at a time,
+CycleTarget jumps to the next enemy in sight and Cancel stops aiming.
+The target never leaves the player's field of view.
+ */
+fn aim(ecs: &SubWorld, map: &Map, lighting: &Lighting, targeting: &mut Targeting, action: Action, target: Point) {
+    let mut fov = <(&Point, &FieldOfView)>::query().filter(component::<Player>());
+    let Some((player_pos, player_fov)) = fov.iter(ecs).next() else {
+        return;
+    };
+
+    if let Some(delta) = action.delta() {
+        if player_fov.visible_tiles.contains(&(target + delta)) {
+            targeting.target = Some(target + delta);
+        }
+    } else if action == Action::CycleTarget {
+        if let Some(next) = next_enemy(ecs, map, lighting, *player_pos, Some(target)) {
+            targeting.target = Some(next);
+        }
+    } else if action == Action::Cancel {
+        targeting.target = None;
+    }
+}
+
+/*
+The enemy in sight that comes after current, going from the nearest to the farthest and back round to the nearest.
+With no current target it is the nearest one.
+ */
+fn next_enemy(ecs: &SubWorld, map: &Map, lighting: &Lighting, player_pos: Point, current: Option<Point>) -> Option<Point> {
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let player_fov = fov.iter(ecs).next()?;
+
+    let mut enemies: Vec<Point> = <&Point>::query()
+        .filter(component::<Enemy>() & !component::<Hidden>())
+        .iter(ecs)
+        .filter(|pos| player_fov.visible_tiles.contains(pos) && lighting.is_lit(map, **pos))
+        .copied()
+        .collect();
+    // Ties are broken by position, so the order doesn't depend on how the enemies are stored.
+    enemies.sort_by_key(|pos| {
+        let delta = *pos - player_pos;
+        (delta.x * delta.x + delta.y * delta.y, pos.y, pos.x)
+    });
+
+    let next = current
+        .and_then(|current| enemies.iter().position(|pos| *pos == current))
+        .map_or(0, |i| (i + 1) % enemies.len());
+    enemies.get(next).copied()
+}
+
+fn is_food(ecs: &SubWorld, entity: Entity) -> bool {
+    ecs.entry_ref(entity).is_ok_and(|entry| entry.get_component::<Food>().is_ok())
+}
+
+/*
+Whether the player can see any enemy: it stands in the player's field of view, on a lit tile.
+ */
+fn enemy_in_sight(ecs: &SubWorld, map: &Map, lighting: &Lighting) -> bool {
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let Some(player_fov) = fov.iter(ecs).next() else {
+        return false;
+    };
 
-        if delta.x != 0 || delta.y != 0 {
-            //We access components with a query
-            //Queries list one or more components and return references, mutable if we use &mut to each instance of that component type.
-            //If we request more than one component type, only entities that have all those components are returned.
-            //The components are grouped together to ensure that each returned set of components only operates on the entity that owns the components.
-            //Legion queries include a filter() function to further refine the set of components required for a query to match an entity.
-            //
-            //This line specifies that only entities with a Point component and a Player tag component should be included in the query.
-            let mut players = <&mut Point>::query().filter(component::<Player>());
-            //The query doesn’t become an iterator until we call iter() or iter_mut(); it’s still a Query
-            //Adding filters before the iterator call limits the types included in the query.
-            //Query filters can require that a component exists but can’t refer to its content.
-            // If we need to filter on the component’s content, we can use the iterator’s filter() function instead.
-            //Calling iter_mut() runs the query we’ve defined and places the results in an iterator.
-
-            players.iter_mut(ecs).for_each(|pos| {
-                let destination = *pos + delta;
-                if map.can_enter_tile(destination){
-                    *pos = destination;
-                    camera.on_player_move(destination);
-                }
-            });
-        }
-    }
-}
\ No newline at end of file
+    let mut enemies = <&Point>::query().filter(component::<Enemy>());
+    enemies.iter(ecs).any(|pos| player_fov.visible_tiles.contains(pos) && lighting.is_lit(map, *pos))
+}
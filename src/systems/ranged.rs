@@ -0,0 +1,40 @@
+use crate::prelude::*;
+
+/*
+    Runs once for every WantsToFire message. The shot flies along the line of fire and hits
+    the first thing standing in its way, which takes the weapon's damage (see combat).
+    A shot that reaches its target tile without meeting anything simply lands there.
+ */
+#[system(for_each)]
+#[read_component(Point)]
+#[read_component(RangedWeapon)]
+#[read_component(Health)]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub fn ranged(
+    entity: &Entity,
+    fire: &WantsToFire,
+    #[resource] map: &Map,
+    #[resource] index: &SpatialIndex,
+    ecs: &SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    commands.remove(*entity);
+
+    let Ok(shooter) = ecs.entry_ref(fire.shooter) else {
+        return;
+    };
+    let (Ok(from), Ok(weapon)) = (shooter.get_component::<Point>(), shooter.get_component::<RangedWeapon>()) else {
+        return;
+    };
+
+    let path = line_of_fire(map, *from, fire.target, weapon.range, |pos| index.is_blocked(map.map_idx(pos.x, pos.y)));
+    let Some(landed) = path.last().and_then(|pos| map.try_idx(*pos)) else {
+        return;
+    };
+    let victim = index.entities_at(landed).iter().copied().find(|other| {
+        ecs.entry_ref(*other).is_ok_and(|other| other.get_component::<Health>().is_ok())
+    });
+    if let Some(victim) = victim {
+        commands.push(((), WantsToAttack { attacker: fire.shooter, victim, damage: weapon.damage, spell: None }));
+    }
+}
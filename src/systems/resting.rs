@@ -0,0 +1,35 @@
+use crate::prelude::*;
+
+/*
+    Runs once for every WantsToRest message. Resting restores a point of mana, and a hit point if the settings allow it.
+    A hungry entity only gets the mana back, and a weak or starving one gets nothing.
+ */
+#[system(for_each)]
+#[read_component(Hunger)]
+#[write_component(Health)]
+#[write_component(Mana)]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub fn resting(
+    entity: &Entity,
+    rest: &WantsToRest,
+    #[resource] settings: &Settings,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    commands.remove(*entity);
+
+    let Ok(mut entry) = ecs.entry_mut(rest.entity) else {
+        return;
+    };
+    let hunger = entry.get_component::<Hunger>().map_or(HungerState::WellFed, |hunger| hunger.state());
+    if let Ok(health) = entry.get_component_mut::<Health>() {
+        if settings.rest_heals && hunger == HungerState::WellFed {
+            health.current = i32::min(health.max, health.current + 1);
+        }
+    }
+    if let Ok(mana) = entry.get_component_mut::<Mana>() {
+        if matches!(hunger, HungerState::WellFed | HungerState::Hungry) {
+            mana.current = i32::min(mana.max, mana.current + 1);
+        }
+    }
+}
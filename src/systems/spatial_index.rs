@@ -0,0 +1,22 @@
+use crate::prelude::*;
+
+/*
+    Rebuilds the spatial index from scratch, so each turn starts from where everything really is.
+ */
+#[system]
+#[read_component(Point)]
+#[read_component(BlocksTile)]
+pub fn spatial_index(
+    ecs: &SubWorld,
+    #[resource] map: &Map,
+    #[resource] index: &mut SpatialIndex,
+) {
+    index.clear(map);
+    <(Entity, &Point, Option<&BlocksTile>)>::query()
+        .iter(ecs)
+        .for_each(|(entity, pos, blocks)| {
+            if let Some(idx) = map.try_idx(*pos) {
+                index.add(*entity, idx, blocks.is_some());
+            }
+        });
+}
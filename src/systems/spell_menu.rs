@@ -0,0 +1,65 @@
+use crate::prelude::*;
+
+/*
+    Draws the cast menu on the HUD layer while it is open: the player's mana, then every spell with what it costs.
+    The spell the menu points at is highlighted, and spells the player can't afford are grayed out.
+ */
+#[system]
+#[read_component(Player)]
+#[read_component(Mana)]
+pub fn spell_menu(
+    ecs: &SubWorld,
+    #[resource] targeting: &Targeting,
+    #[resource] spellbook: &Spellbook,
+    #[resource] keymap: &Keymap,
+) {
+    let Some(selected) = targeting.menu else {
+        return;
+    };
+    let mut mana = <&Mana>::query().filter(component::<Player>());
+    let Some(mana) = mana.iter(ecs).next() else {
+        return;
+    };
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    draw_batch.print_color(
+        Point::new(4, 4),
+        format!("Cast which spell? Mana: {} / {}", mana.current, mana.max),
+        ColorPair::new(WHITE, BLACK),
+    );
+    for (i, spell) in spellbook.spells.iter().enumerate() {
+        let color = if i == selected {
+            YELLOW
+        } else if spell.mana > mana.current {
+            GRAY
+        } else {
+            WHITE
+        };
+        let pointer = if i == selected { '>' } else { ' ' };
+        #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+        let y = 6 + i as i32;
+        draw_batch.print_color(
+            Point::new(4, y),
+            format!("{pointer} {} ({} mana)", spell.name, spell.mana),
+            ColorPair::new(color, BLACK),
+        );
+    }
+
+    let key = |action| keymap.key_for(action).map_or_else(|| "(unbound)".to_string(), |key| format!("{key:?}"));
+    #[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+    let y = 7 + spellbook.spells.len() as i32;
+    draw_batch.print_color(
+        Point::new(4, y),
+        format!(
+            "{}/{} choose, {} casts, {} cancels.",
+            key(Action::MoveNorth),
+            key(Action::MoveSouth),
+            key(Action::Cast),
+            key(Action::Cancel)
+        ),
+        ColorPair::new(GRAY, BLACK),
+    );
+
+    draw_batch.submit(10000).expect("Batch error");
+}
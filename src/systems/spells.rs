@@ -0,0 +1,114 @@
+use crate::prelude::*;
+
+/*
+    Runs once for every WantsToCast message. The spell flies along the line of fire like a shot, and comes down
+    on the first thing in its way. A caster without the mana for it, or with nowhere to aim it, fizzles without paying.
+    Damage and status effects reach every creature in the area of effect, the caster included, so a fireball is best
+    thrown from a distance. Blink moves the caster to where the spell came down, or the nearest free tile short of it.
+    Spells with a range of 0 come down on the caster. Haste and Slow cure each other.
+    Damage spells hit everything in the area like a blow from the caster (see combat), so the caster earns the xp of whatever they kill.
+    Damage spells go off with a bang that can be heard like a fight, where they come down.
+ */
+#[system(for_each)]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(Health)]
+#[write_component(Mana)]
+#[allow(clippy::too_many_arguments, clippy::trivially_copy_pass_by_ref)]
+pub fn spells(
+    entity: &Entity,
+    cast: &WantsToCast,
+    #[resource] map: &Map,
+    #[resource] spellbook: &Spellbook,
+    #[resource] camera: &mut Camera,
+    #[resource] index: &mut SpatialIndex,
+    #[resource] noises: &mut Noises,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    commands.remove(*entity);
+
+    let Some(spell) = spellbook.spells.get(cast.spell) else {
+        return;
+    };
+    let Ok(mut caster) = ecs.entry_mut(cast.caster) else {
+        return;
+    };
+    let Ok(from) = caster.get_component::<Point>().copied() else {
+        return;
+    };
+    let path = if spell.range == 0 {
+        vec![from]
+    } else {
+        line_of_fire(map, from, cast.target, spell.range, |pos| index.is_blocked(map.map_idx(pos.x, pos.y)))
+    };
+    let Some(impact) = path.last().copied() else {
+        return;
+    };
+    match caster.get_component_mut::<Mana>() {
+        Ok(mana) if mana.current >= spell.mana => mana.current -= spell.mana,
+        _ => return,
+    }
+
+    match spell.effect {
+        SpellEffect::Blink => {
+            let is_player = caster.get_component::<Player>().is_ok();
+            let fov = caster.get_component::<FieldOfView>().ok().map(FieldOfView::clone_dirty);
+            let landing = path.iter().rev().copied().find(|pos| {
+                map.tile(*pos).is_some_and(|tile| !tile.is_hazard()) && !index.is_blocked(map.map_idx(pos.x, pos.y))
+            });
+            let Some(landing) = landing else {
+                return;
+            };
+            index.move_entity(cast.caster, map.map_idx(from.x, from.y), map.map_idx(landing.x, landing.y));
+            commands.add_component(cast.caster, landing);
+            commands.push(((), EnteredTile { entity: cast.caster, destination: landing }));
+            if let Some(fov) = fov {
+                commands.add_component(cast.caster, fov);
+            }
+            if is_player {
+                camera.on_player_move(landing, map);
+            }
+        }
+        SpellEffect::Damage(damage) => {
+            noises.make(impact, COMBAT_NOISE);
+            for victim in victims(ecs, map, index, impact, spell.radius) {
+                commands.push(((), WantsToAttack { attacker: cast.caster, victim, damage, spell: Some(cast.spell) }));
+            }
+        }
+        SpellEffect::Confuse(turns) => {
+            for victim in victims(ecs, map, index, impact, spell.radius) {
+                commands.add_component(victim, Confused { turns });
+            }
+        }
+        SpellEffect::Poison { damage, turns } => {
+            for victim in victims(ecs, map, index, impact, spell.radius) {
+                commands.add_component(victim, Poisoned { damage, turns });
+            }
+        }
+        SpellEffect::Haste(turns) => {
+            for victim in victims(ecs, map, index, impact, spell.radius) {
+                commands.remove_component::<Slowed>(victim);
+                commands.add_component(victim, Hasted { turns });
+            }
+        }
+        SpellEffect::Slow(turns) => {
+            for victim in victims(ecs, map, index, impact, spell.radius) {
+                commands.remove_component::<Hasted>(victim);
+                commands.add_component(victim, Slowed { turns });
+            }
+        }
+    }
+}
+
+/*
+Every creature in the area of effect.
+ */
+fn victims(ecs: &SubWorld, map: &Map, index: &SpatialIndex, impact: Point, radius: i32) -> Vec<Entity> {
+    area_of_effect(map, impact, radius)
+        .into_iter()
+        .flat_map(|pos| index.entities_at(map.map_idx(pos.x, pos.y)).iter().copied())
+        .filter(|entity| ecs.entry_ref(*entity).is_ok_and(|entry| entry.get_component::<Health>().is_ok()))
+        .collect()
+}
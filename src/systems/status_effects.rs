@@ -0,0 +1,55 @@
+use crate::prelude::*;
+
+/*
+    Runs at the start of every turn, so every status effect wears off once per turn, however often whoever it is on
+    gets to act. It works the same way for the player and the monsters.
+    Poison hurts first; wounds it deals the player are tallied as Poison, and monsters it kills are removed.
+    Then every effect loses a turn, and those that have run out are removed.
+ */
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[write_component(Health)]
+#[write_component(Poisoned)]
+#[write_component(Confused)]
+#[write_component(Hasted)]
+#[write_component(Slowed)]
+pub fn status_effects(
+    ecs: &mut SubWorld,
+    #[resource] map: &Map,
+    #[resource] index: &mut SpatialIndex,
+    #[resource] stats: &mut GameStats,
+    commands: &mut CommandBuffer,
+) {
+    let mut dead = Vec::new();
+    let mut poisoned = <(Entity, &Point, &Poisoned, &mut Health, Option<&Player>)>::query();
+    poisoned.for_each_mut(ecs, |(entity, pos, poison, health, player)| {
+        health.current -= poison.damage;
+        // The player's death is noticed by end_turn.
+        if player.is_some() {
+            stats.player_hurt("Poison", poison.damage);
+        } else if health.current <= 0 {
+            dead.push((*entity, *pos));
+        }
+    });
+
+    wear_off::<Poisoned>(ecs, commands);
+    wear_off::<Confused>(ecs, commands);
+    wear_off::<Hasted>(ecs, commands);
+    wear_off::<Slowed>(ecs, commands);
+
+    // Dead monsters go last: a command buffer can't take a component off an entity it has already removed.
+    for (entity, pos) in dead {
+        commands.remove(entity);
+        index.remove(entity, map.map_idx(pos.x, pos.y));
+    }
+}
+
+fn wear_off<T: StatusEffect>(ecs: &mut SubWorld, commands: &mut CommandBuffer) {
+    <(Entity, &mut T)>::query().for_each_mut(ecs, |(entity, effect)| {
+        *effect.turns_mut() -= 1;
+        if effect.turns() < 1 {
+            commands.remove_component::<T>(*entity);
+        }
+    });
+}
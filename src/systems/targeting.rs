@@ -0,0 +1,89 @@
+use crate::prelude::*;
+
+/*
+    While the player is aiming, marks the line of fire on the HUD layer: a yellow dot over every tile the shot
+    would fly over, and a red cross where it would come down. A spell's area of effect around that point is marked
+    with orange rings. Only enemies the player can see are taken into account,
+    so the line shows what the player expects to happen, not what is really in the way.
+    A line at the top of the screen says what is being aimed at, and which keys fire, pick another target or cancel.
+    The HUD layer is four times the resolution of the map, so each mark sits in the middle of its tile's 4x4 block.
+ */
+#[system]
+#[read_component(Point)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(RangedWeapon)]
+#[read_component(BlocksTile)]
+#[read_component(Hidden)]
+#[read_component(Name)]
+#[allow(clippy::too_many_arguments)]
+pub fn targeting(
+    ecs: &SubWorld,
+    #[resource] targeting: &Targeting,
+    #[resource] camera: &Camera,
+    #[resource] map: &Map,
+    #[resource] lighting: &Lighting,
+    #[resource] keymap: &Keymap,
+    #[resource] spellbook: &Spellbook,
+    #[resource] index: &SpatialIndex,
+) {
+    let Some(target) = targeting.target else {
+        return;
+    };
+    let mut player = <(Entity, &Point, &FieldOfView)>::query().filter(component::<Player>());
+    let Some((player, player_pos, player_fov)) = player.iter(ecs).next() else {
+        return;
+    };
+    // A spell has a range of its own; anything else is a shot from the player's ranged weapon.
+    let spell = targeting.spell.and_then(|spell| spellbook.spells.get(spell));
+    let weapon_range = || ecs.entry_ref(*player).ok()?.get_component::<RangedWeapon>().ok().map(|weapon| weapon.range);
+    let Some(range) = spell.map(|spell| spell.range).or_else(weapon_range) else {
+        return;
+    };
+
+    let seen = |pos: &Point| player_fov.visible_tiles.contains(pos) && lighting.is_lit(map, *pos);
+    let blocker_at = |pos: Point| {
+        let idx = map.try_idx(pos).filter(|_| seen(&pos))?;
+        index.entities_at(idx).iter().copied().find(|entity| {
+            ecs.entry_ref(*entity).is_ok_and(|entry| {
+                entry.get_component::<BlocksTile>().is_ok()
+                    && entry.get_component::<Player>().is_err()
+                    && entry.get_component::<Hidden>().is_err()
+            })
+        })
+    };
+    let path = line_of_fire(map, *player_pos, target, range, |pos| blocker_at(pos).is_some());
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+    let offset = Point::new(camera.left_x, camera.top_y);
+    if let (Some(spell), Some(impact)) = (spell, path.last()) {
+        for pos in area_of_effect(map, *impact, spell.radius) {
+            draw_batch.set((pos - offset) * 4 + Point::new(2, 2), ColorPair::new(ORANGE, BLACK), to_cp437('o'));
+        }
+    }
+    for (i, pos) in path.iter().enumerate() {
+        let (color, glyph) = if i + 1 == path.len() { (RED, 'X') } else { (YELLOW, '*') };
+        draw_batch.set((*pos - offset) * 4 + Point::new(2, 2), ColorPair::new(color, BLACK), to_cp437(glyph));
+    }
+
+    let aimed_at = path
+        .last()
+        .and_then(|landed| blocker_at(*landed))
+        .and_then(|blocker| ecs.entry_ref(blocker).ok()?.get_component::<Name>().ok().map(|name| name.0.clone()))
+        .unwrap_or_else(|| "nothing".to_string());
+    let key = |action| keymap.key_for(action).map_or_else(|| "(unbound)".to_string(), |key| format!("{key:?}"));
+    draw_batch.print_color_centered(
+        1,
+        format!(
+            "Aiming {} at {aimed_at}. {} fires, {} picks the next target, {} cancels.",
+            spell.map_or("your sling", |spell| spell.name.as_str()),
+            key(Action::Fire),
+            key(Action::CycleTarget),
+            key(Action::Cancel)
+        ),
+        ColorPair::new(YELLOW, BLACK),
+    );
+
+    draw_batch.submit(10000).expect("Batch error");
+}
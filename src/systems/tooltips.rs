@@ -0,0 +1,71 @@
+use crate::prelude::*;
+
+/*
+    The mouse position is stored as a Point resource in screen coordinates of the map layer.
+    Adding the camera offset turns it into a world position, and the spatial index says which named entities stand there.
+    Entities that can be hurt also show their hit points, and any status effects on them.
+    The HUD layer uses a font four times smaller than the map layer, so the screen position is scaled up to match it.
+ */
+#[system]
+#[read_component(Point)]
+#[read_component(Name)]
+#[read_component(Render)]
+#[read_component(Health)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(Hidden)]
+#[read_component(Poisoned)]
+#[read_component(Confused)]
+#[read_component(Hasted)]
+#[read_component(Slowed)]
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub fn tooltips(
+    ecs: &SubWorld,
+    #[resource] mouse_pos: &Point,
+    #[resource] camera: &Camera,
+    #[resource] map: &Map,
+    #[resource] lighting: &Lighting,
+    #[resource] index: &SpatialIndex,
+) {
+    let offset = Point::new(camera.left_x, camera.top_y);
+    let map_pos = *mouse_pos + offset;
+
+    let mut fov = <&FieldOfView>::query().filter(component::<Player>());
+    let Some(player_fov) = fov.iter(ecs).next() else {
+        return;
+    };
+
+    let mut draw_batch = DrawBatch::new();
+    draw_batch.target(2);
+
+    let Some(idx) = map.try_idx(map_pos) else {
+        return;
+    };
+    if !player_fov.visible_tiles.contains(&map_pos) || !lighting.is_lit(map, map_pos) {
+        return;
+    }
+
+    for entity in index.entities_at(idx) {
+        let Ok(entry) = ecs.entry_ref(*entity) else {
+            continue;
+        };
+        let Ok(name) = entry.get_component::<Name>() else {
+            continue;
+        };
+        if entry.get_component::<Render>().is_err() || entry.get_component::<Hidden>().is_ok() {
+            continue;
+        }
+        let screen_pos = *mouse_pos * 4;
+        let mut display = if let Ok(health) = entry.get_component::<Health>() {
+            format!("{} : {} / {} hp", &name.0, health.current, health.max)
+        } else {
+            name.0.clone()
+        };
+        for effect in describe_status_effects(&entry) {
+            display = format!("{display}, {effect}");
+        }
+        draw_batch.print(screen_pos, &display);
+    }
+
+    draw_batch.submit(10100).expect("Batch error");
+}
@@ -0,0 +1,111 @@
+use crate::prelude::*;
+
+const DART_DAMAGE: i32 = 2;
+const PIT_DAMAGE: i32 = 2;
+
+/*
+    Runs once for every EnteredTile message, and sets off any trap on the tile that was entered.
+    Monsters are just as likely to blunder into a trap as the player.
+    A trap that goes off is no longer hidden, and stays where it is to catch the next victim.
+    Every trap makes some noise going off; an alarm is heard much further away.
+    The spatial index tells us what is on the tile without searching every trap on the level.
+ */
+#[system(for_each)]
+#[read_component(Point)]
+#[read_component(Trap)]
+#[read_component(Player)]
+#[read_component(FieldOfView)]
+#[read_component(Name)]
+#[write_component(Health)]
+#[allow(clippy::too_many_arguments, clippy::trivially_copy_pass_by_ref)]
+pub fn traps(
+    entity: &Entity,
+    entered: &EnteredTile,
+    #[resource] map: &Map,
+    #[resource] rng: &mut RandomNumberGenerator,
+    #[resource] camera: &mut Camera,
+    #[resource] turn_state: &mut TurnState,
+    #[resource] index: &mut SpatialIndex,
+    #[resource] stats: &mut GameStats,
+    #[resource] noises: &mut Noises,
+    ecs: &mut SubWorld,
+    commands: &mut CommandBuffer,
+) {
+    commands.remove(*entity);
+
+    let Some(idx) = map.try_idx(entered.destination) else {
+        return;
+    };
+    let Some((trap, kind, name)) = index.entities_at(idx).iter().find_map(|entity| {
+        ecs.entry_ref(*entity).ok().and_then(|entry| {
+            let name = entry.get_component::<Name>().map_or_else(|_| "Trap".to_string(), |name| name.0.clone());
+            entry.get_component::<Trap>().ok().map(|trap| (*entity, trap.kind, name))
+        })
+    }) else {
+        return;
+    };
+    let Ok(victim) = ecs.entry_ref(entered.entity) else {
+        return;
+    };
+    let is_player = victim.get_component::<Player>().is_ok();
+    let fov = victim.get_component::<FieldOfView>().ok().map(FieldOfView::clone_dirty);
+
+    commands.remove_component::<Hidden>(trap);
+    if kind != TrapKind::Alarm {
+        noises.make(entered.destination, TRAP_NOISE);
+    }
+
+    match kind {
+        TrapKind::Dart => {
+            if hurt(ecs, stats, entered.entity, DART_DAMAGE, &name) {
+                commands.remove(entered.entity);
+                index.remove(entered.entity, idx);
+            }
+        }
+        TrapKind::Teleport => {
+            // Try a handful of random spots until one of them is plain floor.
+            let destination = (0..100)
+                .map(|_| Point::new(rng.range(0, map.width), rng.range(0, map.height)))
+                .find(|pos| map.tile(*pos) == Some(TileType::Floor)
+                    && !index.is_blocked(map.map_idx(pos.x, pos.y)));
+            if let Some(destination) = destination {
+                index.move_entity(entered.entity, idx, map.map_idx(destination.x, destination.y));
+                commands.add_component(entered.entity, destination);
+                if let Some(fov) = fov {
+                    commands.add_component(entered.entity, fov);
+                }
+                if is_player {
+                    camera.look_at(destination, map);
+                }
+            }
+        }
+        TrapKind::Alarm => noises.make(entered.destination, ALARM_NOISE),
+        TrapKind::Pit => {
+            hurt(ecs, stats, entered.entity, PIT_DAMAGE, &name);
+            if is_player {
+                *turn_state = TurnState::NextLevel;
+            } else {
+                // Monsters that fall are gone from this level, dead or alive.
+                commands.remove(entered.entity);
+                index.remove(entered.entity, idx);
+            }
+        }
+    }
+}
+
+/*
+    Takes hit points from the victim, and tells whether it was a monster that ran out, for the caller to remove.
+    The player's death is noticed by end_turn. The player's wounds are tallied under the trap's name.
+ */
+fn hurt(ecs: &mut SubWorld, stats: &mut GameStats, victim: Entity, damage: i32, trap: &str) -> bool {
+    let mut entry = ecs.entry_mut(victim).unwrap();
+    let is_player = entry.get_component::<Player>().is_ok();
+    let Ok(health) = entry.get_component_mut::<Health>() else {
+        return false;
+    };
+    health.current -= damage;
+    if is_player {
+        stats.player_hurt(trap, damage);
+    }
+    health.current <= 0 && !is_player
+}
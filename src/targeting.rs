@@ -0,0 +1,93 @@
+use crate::prelude::*;
+
+/*
+Targeting mode: while the player aims a ranged weapon or a spell, target is the tile they are aiming at.
+It is None the rest of the time. spell is the index in the Spellbook of the spell being aimed, or None for the weapon.
+Spells are picked from the cast menu first: while it is open, menu is the index of the spell it points at.
+ */
+#[derive(Default)]
+pub struct Targeting {
+    pub target: Option<Point>,
+    pub spell: Option<usize>,
+    pub menu: Option<usize>,
+}
+
+/*
+The tiles a shot from `from` towards `to` flies over, in order, along a Bresenham line.
+The shot comes down on the target, after range tiles, or on the first tile where blocked() finds something in the way,
+whichever comes first. Walls and closed doors stop it on the tile in front of them.
+ */
+#[allow(clippy::cast_sign_loss)]
+pub fn line_of_fire(map: &Map, from: Point, to: Point, range: i32, blocked: impl Fn(Point) -> bool) -> Vec<Point> {
+    let mut path = Vec::new();
+    for pos in line2d_bresenham(from, to).into_iter().skip(1).take(range.max(0) as usize) {
+        if map.try_idx(pos).is_none_or(|idx| map.is_opaque(idx)) {
+            break;
+        }
+        path.push(pos);
+        if blocked(pos) {
+            break;
+        }
+    }
+    path
+}
+
+/*
+The tiles a blast centered on center reaches: those within radius tiles that can be seen from the center,
+so walls shelter whatever is behind them. They come sorted by position, so effects always happen in the same order.
+ */
+pub fn area_of_effect(map: &Map, center: Point, radius: i32) -> Vec<Point> {
+    #[allow(clippy::cast_precision_loss)]
+    let reach = radius as f32;
+    let mut area: Vec<Point> = field_of_view_set(center, radius, map)
+        .into_iter()
+        .filter(|pos| map.in_bounds(*pos) && DistanceAlg::Pythagoras.distance2d(center, *pos) <= reach)
+        .collect();
+    area.sort_by_key(|pos| (pos.y, pos.x));
+    area
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An open 10x10 floor with a wall at x 6, y 2.
+    fn map() -> Map {
+        let mut map = Map::new(10, 10);
+        let wall = map.map_idx(6, 2);
+        map.tiles[wall] = TileType::Wall;
+        map
+    }
+
+    #[test]
+    fn line_of_fire_skips_the_shooter_and_stops_at_range() {
+        let path = line_of_fire(&map(), Point::new(1, 5), Point::new(8, 5), 3, |_| false);
+        assert_eq!(path, vec![Point::new(2, 5), Point::new(3, 5), Point::new(4, 5)]);
+    }
+
+    #[test]
+    fn line_of_fire_stops_short_of_walls() {
+        let path = line_of_fire(&map(), Point::new(1, 2), Point::new(9, 2), 10, |_| false);
+        assert_eq!(path.last(), Some(&Point::new(5, 2)));
+    }
+
+    #[test]
+    fn line_of_fire_stops_at_whatever_is_in_the_way() {
+        let path = line_of_fire(&map(), Point::new(1, 5), Point::new(8, 5), 10, |pos| pos == Point::new(4, 5));
+        assert_eq!(path.last(), Some(&Point::new(4, 5)));
+        assert_eq!(path.len(), 3);
+    }
+
+    #[test]
+    fn area_of_effect_is_round_sheltered_and_sorted() {
+        let area = area_of_effect(&map(), Point::new(5, 5), 2);
+        assert!(area.contains(&Point::new(5, 3)));
+        assert!(area.contains(&Point::new(6, 6)));
+        assert!(!area.contains(&Point::new(7, 7)));
+        let sheltered = area_of_effect(&map(), Point::new(4, 2), 3);
+        assert!(!sheltered.contains(&Point::new(7, 2)));
+        let mut sorted = area.clone();
+        sorted.sort_by_key(|pos| (pos.y, pos.x));
+        assert_eq!(area, sorted);
+    }
+}
@@ -0,0 +1,303 @@
+use crate::prelude::*;
+use crossterm::{
+    cursor,
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers, MouseEventKind},
+    execute, queue,
+    style::{Color, Print, ResetColor, SetBackgroundColor, SetForegroundColor},
+    terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use std::io::{self, Stdout, Write};
+use std::time::{Duration, Instant};
+
+/*
+A text-mode front end, for playing over SSH or on machines without a GPU. Start the game with --terminal to use it.
+The game doesn't know the difference: tick() draws into the same three consoles as in the window,
+they just aren't attached to one. After each frame the consoles are read back and printed with crossterm.
+Every map tile takes two terminal columns, since terminal cells are about twice as tall as they are wide.
+That also makes the 40x25 view of the map fill a standard 80x25 terminal.
+ */
+
+// The window is capped at 30 frames per second; so is the terminal.
+const FRAME_TIME: Duration = Duration::from_millis(33);
+// Map tiles are 32 pixels square. The consoles turn the mouse position in pixels into tile coordinates.
+const TILE_SIZE: i32 = 32;
+const COLUMNS: i32 = DISPLAY_WIDTH * 2;
+const ROWS: i32 = DISPLAY_HEIGHT;
+
+#[derive(Clone, Copy, PartialEq)]
+struct Cell {
+    glyph: char,
+    fg: RGBA,
+    bg: RGBA,
+}
+
+/*
+Runs the game in the terminal until it quits, or the player presses Ctrl-C or Ctrl-Q.
+The terminal is put back the way it was afterwards, even if the game loop failed.
+ */
+pub fn run<GS: GameState>(mut gs: GS) -> BError {
+    let mut ctx = headless_context();
+    let mut stdout = io::stdout();
+
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, cursor::Hide, Clear(ClearType::All))?;
+    let result = game_loop(&mut gs, &mut ctx, &mut stdout);
+    execute!(stdout, ResetColor, cursor::Show, DisableMouseCapture, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+/*
+A BTerm with the same consoles as the window, but no window. Fonts are never used, so every console gets font 0.
+ */
+#[allow(clippy::cast_sign_loss)]
+fn headless_context() -> BTerm {
+    let width_pixels = (DISPLAY_WIDTH * TILE_SIZE) as u32;
+    let height_pixels = (DISPLAY_HEIGHT * TILE_SIZE) as u32;
+    let mut ctx = BTerm {
+        width_pixels,
+        height_pixels,
+        original_width_pixels: width_pixels,
+        original_height_pixels: height_pixels,
+        fps: 0.0,
+        frame_time_ms: 0.0,
+        active_console: 0,
+        key: None,
+        mouse_pos: (0, 0),
+        left_click: false,
+        shift: false,
+        control: false,
+        alt: false,
+        web_button: None,
+        quitting: false,
+        post_scanlines: false,
+        post_screenburn: false,
+        screen_burn_color: RGB::named(CYAN),
+        mouse_visible: false,
+    };
+    ctx.register_console(SimpleConsole::init(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32), 0);
+    ctx.register_console_no_bg(SimpleConsole::init(DISPLAY_WIDTH as u32, DISPLAY_HEIGHT as u32), 0);
+    ctx.register_console_no_bg(SimpleConsole::init((SCREEN_WIDTH * 2) as u32, (SCREEN_HEIGHT * 2) as u32), 0);
+    ctx
+}
+
+fn game_loop<GS: GameState>(gs: &mut GS, ctx: &mut BTerm, stdout: &mut Stdout) -> BError {
+    let mut last_frame = Vec::new();
+    while !ctx.quitting {
+        let frame_start = Instant::now();
+
+        // Like the window, the game gets at most one key per frame; any others wait for the next frame.
+        ctx.key = None;
+        while ctx.key.is_none() && event::poll(Duration::ZERO)? {
+            match event::read()? {
+                Event::Key(key) if is_quit(key) => return Ok(()),
+                Event::Key(key) => ctx.key = virtual_key(key.code),
+                Event::Mouse(mouse) if matches!(mouse.kind, MouseEventKind::Moved | MouseEventKind::Drag(_)) => {
+                    // Aim at the middle of the tile under the mouse.
+                    ctx.mouse_pos = (
+                        i32::from(mouse.column / 2) * TILE_SIZE + TILE_SIZE / 2,
+                        i32::from(mouse.row) * TILE_SIZE + TILE_SIZE / 2,
+                    );
+                }
+                // Whatever was on screen may be gone, so draw everything again.
+                Event::Resize(..) => last_frame.clear(),
+                _ => {}
+            }
+        }
+
+        gs.tick(ctx);
+        let frame = read_frame();
+        draw(stdout, &frame, &last_frame)?;
+        last_frame = frame;
+
+        if let Some(rest) = FRAME_TIME.checked_sub(frame_start.elapsed()) {
+            std::thread::sleep(rest);
+        }
+    }
+    Ok(())
+}
+
+// Raw mode swallows Ctrl-C, so the loop looks out for it itself.
+fn is_quit(key: KeyEvent) -> bool {
+    key.modifiers.contains(KeyModifiers::CONTROL) && matches!(key.code, KeyCode::Char('c' | 'q'))
+}
+
+/*
+The key the window would have reported. Terminals can't tell the numeric keypad apart from the other keys,
+so the keypad bindings are out of reach, but the arrow keys and vi keys cover the same actions.
+ */
+fn virtual_key(code: KeyCode) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode as Key;
+    let key = match code {
+        KeyCode::Up => Key::Up,
+        KeyCode::Down => Key::Down,
+        KeyCode::Left => Key::Left,
+        KeyCode::Right => Key::Right,
+        KeyCode::Home => Key::Home,
+        KeyCode::End => Key::End,
+        KeyCode::PageUp => Key::PageUp,
+        KeyCode::PageDown => Key::PageDown,
+        KeyCode::Insert => Key::Insert,
+        KeyCode::Delete => Key::Delete,
+        KeyCode::Backspace => Key::Back,
+        KeyCode::Enter => Key::Return,
+        KeyCode::Tab => Key::Tab,
+        KeyCode::Esc => Key::Escape,
+        KeyCode::Char(c) if c.is_ascii_alphabetic() => return key_from_name(&c.to_ascii_uppercase().to_string()),
+        KeyCode::Char(c) if c.is_ascii_digit() => return key_from_name(&format!("Key{c}")),
+        KeyCode::Char(' ') => Key::Space,
+        KeyCode::Char('.') => Key::Period,
+        KeyCode::Char(',') => Key::Comma,
+        KeyCode::Char(';') => Key::Semicolon,
+        KeyCode::Char('/') => Key::Slash,
+        KeyCode::Char('-') => Key::Minus,
+        KeyCode::Char('\'') => Key::Apostrophe,
+        KeyCode::Char('=') => Key::Equals,
+        KeyCode::Char('+') => Key::NumpadAdd,
+        KeyCode::Char('*') => Key::NumpadMultiply,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/*
+Reads the three consoles back into one grid of terminal cells.
+Entities are drawn over the map, on the map's background. HUD text is laid over both, each run of text centered
+where it sat on the HUD layer; two or more blanks in a row split a line into separate runs, so marks on the map
+like the line of fire stay over their own tiles. The HUD has four times the rows of the terminal, so runs that
+would land on text already drawn are pushed down to the next free row instead.
+ */
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+fn read_frame() -> Vec<Cell> {
+    let bi = BACKEND_INTERNAL.lock();
+    let layer = |i: usize| {
+        bi.consoles[i].console.as_any().downcast_ref::<SimpleConsole>().expect("Terminal consoles are simple consoles")
+    };
+    let (map, entities, hud) = (layer(0), layer(1), layer(2));
+    let blank = Cell { glyph: ' ', fg: RGBA::named(WHITE), bg: RGBA::named(BLACK) };
+    let mut frame = vec![blank; (COLUMNS * ROWS) as usize];
+
+    for (i, (tile, entity)) in map.tiles.iter().zip(&entities.tiles).enumerate() {
+        // Consoles store their rows from the bottom of the screen up.
+        let (x, y) = ((i % map.width as usize) as i32, (map.height as usize - 1 - i / map.width as usize) as i32);
+        let top = if entity.glyph == 0 || entity.glyph == 32 { tile } else { entity };
+        let idx = (y * COLUMNS + x * 2) as usize;
+        frame[idx] = Cell { glyph: ascii_glyph(top.glyph), fg: top.fg, bg: tile.bg };
+        frame[idx + 1] = Cell { glyph: ' ', fg: top.fg, bg: tile.bg };
+    }
+
+    let mut hud_cells = vec![false; frame.len()];
+    for (y, row) in hud.tiles.chunks(hud.width as usize).rev().enumerate() {
+        for (start, end) in text_runs(row) {
+            let text = &row[start .. end];
+            let len = text.len() as i32;
+            let center = (start + end) as i32 / 4;
+            let x0 = (center - len / 2).clamp(0, i32::max(0, COLUMNS - len));
+            let columns = x0 .. i32::min(COLUMNS, x0 + len);
+            let Some(y0) = (y as i32 / 4 .. ROWS)
+                .find(|y0| columns.clone().all(|x| !hud_cells[(y0 * COLUMNS + x) as usize]))
+            else {
+                continue;
+            };
+            for (x, tile) in columns.zip(text) {
+                let idx = (y0 * COLUMNS + x) as usize;
+                frame[idx] = Cell { glyph: hud_glyph(tile.glyph), fg: tile.fg, bg: RGBA::named(BLACK) };
+                hud_cells[idx] = true;
+            }
+        }
+    }
+    frame
+}
+
+/*
+The HUD font is plain text, so its ASCII characters mean what they say; ascii_glyph() would turn '/' into a torch.
+ */
+fn hud_glyph(glyph: FontCharType) -> char {
+    u8::try_from(glyph).ok().filter(u8::is_ascii).map_or_else(|| ascii_glyph(glyph), char::from)
+}
+
+/*
+Where the runs of text on a row of the HUD start and end. A single blank belongs to the text around it.
+ */
+fn text_runs(row: &[Tile]) -> Vec<(usize, usize)> {
+    let is_blank = |tile: &Tile| tile.glyph == 0 || tile.glyph == 32;
+    let mut runs: Vec<(usize, usize)> = Vec::new();
+    for (x, _) in row.iter().enumerate().filter(|(_, tile)| !is_blank(tile)) {
+        match runs.last_mut() {
+            Some((_, end)) if x <= *end + 1 => *end = x + 1,
+            _ => runs.push((x, x + 1)),
+        }
+    }
+    runs
+}
+
+/*
+Prints the cells that changed since the last frame. An empty last frame redraws everything.
+ */
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+fn draw(stdout: &mut Stdout, frame: &[Cell], last_frame: &[Cell]) -> BError {
+    for (i, cell) in frame.iter().enumerate() {
+        if last_frame.get(i) == Some(cell) {
+            continue;
+        }
+        let (x, y) = (i % COLUMNS as usize, i / COLUMNS as usize);
+        queue!(
+            stdout,
+            cursor::MoveTo(x as u16, y as u16),
+            SetForegroundColor(term_color(cell.fg)),
+            SetBackgroundColor(term_color(cell.bg)),
+            Print(cell.glyph)
+        )?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+fn term_color(color: RGBA) -> Color {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0) as u8;
+    Color::Rgb { r: channel(color.r), g: channel(color.g), b: channel(color.b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn letters_and_digits_map_to_their_keys_whatever_the_case() {
+        assert_eq!(virtual_key(KeyCode::Char('k')), Some(VirtualKeyCode::K));
+        assert_eq!(virtual_key(KeyCode::Char('K')), Some(VirtualKeyCode::K));
+        assert_eq!(virtual_key(KeyCode::Char('5')), Some(VirtualKeyCode::Key5));
+        assert_eq!(virtual_key(KeyCode::Enter), Some(VirtualKeyCode::Return));
+        assert_eq!(virtual_key(KeyCode::F(1)), None);
+    }
+
+    #[test]
+    fn every_default_action_can_be_reached_from_a_terminal() {
+        let keymap = Keymap::default();
+        let special = [KeyCode::Up, KeyCode::Down, KeyCode::Left, KeyCode::Right, KeyCode::Enter, KeyCode::Esc];
+        let reachable: Vec<Action> = (' ' ..= '~')
+            .map(KeyCode::Char)
+            .chain(special)
+            .filter_map(virtual_key)
+            .filter_map(|key| keymap.action(key))
+            .collect();
+        let actions = [
+            Action::MoveNorth, Action::MoveSouth, Action::MoveEast, Action::MoveWest,
+            Action::MoveNorthEast, Action::MoveNorthWest, Action::MoveSouthEast, Action::MoveSouthWest,
+            Action::Wait, Action::Eat, Action::Descend, Action::Look, Action::Search,
+        ];
+        for action in actions {
+            assert!(reachable.contains(&action), "{action:?} has no key a terminal can send");
+        }
+    }
+
+    #[test]
+    fn single_blanks_join_text_runs_and_wider_gaps_split_them() {
+        let row: Vec<Tile> = "ab c   d  "
+            .chars()
+            .map(|c| Tile { glyph: to_cp437(c), fg: RGBA::named(WHITE), bg: RGBA::named(BLACK) })
+            .collect();
+        assert_eq!(text_runs(&row), vec![(0, 4), (7, 8)]);
+    }
+}
@@ -0,0 +1,125 @@
+use crate::prelude::*;
+
+/*
+A theme decides how each kind of tile looks on a level, and which hazards its pools and rivers are made of.
+Each level picks one when it is built.
+ */
+pub trait MapTheme: Sync + Send {
+    fn tile_to_render(&self, tile: TileType) -> (FontCharType, ColorPair);
+
+    // The map builder picks each pool from this list, so repeating an entry makes it more common.
+    fn hazards(&self) -> &'static [TileType];
+
+    // How brightly the whole level is lit before any light sources are added, from 0.0 to 1.0.
+    fn ambient_light(&self) -> f32;
+}
+
+/*
+The theme is stored in the map as a plain enum, which is cheap to copy and compare; theme() finds the MapTheme it names.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Hash)]
+pub enum ThemeKind {
+    Dungeon,
+    Forest,
+    LavaCavern,
+}
+
+impl ThemeKind {
+    pub fn theme(self) -> &'static dyn MapTheme {
+        match self {
+            ThemeKind::Dungeon => &DungeonTheme,
+            ThemeKind::Forest => &ForestTheme,
+            ThemeKind::LavaCavern => &LavaCavernTheme,
+        }
+    }
+}
+
+/*
+Stone walls and flagstones. Open doors, water and lava use the floor tile, tinted so they stand out. Chasms are left black.
+ */
+pub struct DungeonTheme;
+
+impl MapTheme for DungeonTheme {
+    fn tile_to_render(&self, tile: TileType) -> (FontCharType, ColorPair) {
+        let (glyph, color) = match tile {
+            TileType::Floor => ('.', WHITE),
+            TileType::Wall => ('#', WHITE),
+            TileType::Door { open: false } => (';', WHITE),
+            TileType::Door { open: true } => ('.', BURLYWOOD),
+            TileType::Exit => ('>', WHITE),
+            TileType::DeepWater => ('.', STEEL_BLUE),
+            TileType::Lava => ('.', ORANGE_RED),
+            TileType::Chasm => (' ', BLACK),
+        };
+        (to_cp437(glyph), ColorPair::new(color, BLACK))
+    }
+
+    fn hazards(&self) -> &'static [TileType] {
+        &[TileType::DeepWater, TileType::DeepWater, TileType::Lava, TileType::Chasm]
+    }
+
+    // Deep underground, only torches and lanterns give any light.
+    fn ambient_light(&self) -> f32 {
+        0.0
+    }
+}
+
+/*
+Walls of trees around mossy clearings, with plenty of ponds.
+ */
+pub struct ForestTheme;
+
+impl MapTheme for ForestTheme {
+    fn tile_to_render(&self, tile: TileType) -> (FontCharType, ColorPair) {
+        let (glyph, color) = match tile {
+            TileType::Floor => ('.', LIGHT_GREEN),
+            TileType::Wall => ('"', WHITE),
+            TileType::Door { open: false } => (';', WHITE),
+            TileType::Door { open: true } => ('.', BURLYWOOD),
+            TileType::Exit => ('>', WHITE),
+            TileType::DeepWater => ('.', DODGER_BLUE),
+            TileType::Lava => ('.', ORANGE_RED),
+            TileType::Chasm => (' ', BLACK),
+        };
+        (to_cp437(glyph), ColorPair::new(color, BLACK))
+    }
+
+    fn hazards(&self) -> &'static [TileType] {
+        &[TileType::DeepWater, TileType::DeepWater, TileType::DeepWater, TileType::Chasm]
+    }
+
+    // Moonlight through the canopy is just enough to see by.
+    fn ambient_light(&self) -> f32 {
+        0.3
+    }
+}
+
+/*
+Scorched rock, glowing cracks and rivers of lava.
+ */
+pub struct LavaCavernTheme;
+
+impl MapTheme for LavaCavernTheme {
+    fn tile_to_render(&self, tile: TileType) -> (FontCharType, ColorPair) {
+        let (glyph, color) = match tile {
+            TileType::Floor => ('.', SANDY_BROWN),
+            TileType::Wall => ('#', INDIAN_RED),
+            TileType::Door { open: false } => (';', WHITE),
+            TileType::Door { open: true } => ('.', BURLYWOOD),
+            TileType::Exit => ('>', WHITE),
+            TileType::DeepWater => ('.', STEEL_BLUE),
+            TileType::Lava => ('.', ORANGE),
+            TileType::Chasm => (' ', BLACK),
+        };
+        (to_cp437(glyph), ColorPair::new(color, BLACK))
+    }
+
+    fn hazards(&self) -> &'static [TileType] {
+        &[TileType::Lava, TileType::Lava, TileType::Lava, TileType::Chasm]
+    }
+
+    // The lava gives off a faint glow everywhere.
+    fn ambient_light(&self) -> f32 {
+        0.12
+    }
+}
@@ -0,0 +1,15 @@
+/*
+Whose turn it is. A NewTurn gives everyone energy (see Initiative). The game then waits for a key press and runs
+the player's move, as often as the player has the energy for, then lets the monsters act until none has any left,
+and starts a new turn. State::tick runs a different schedule for each of these; end_turn picks the next one.
+NextLevel and GameOver interrupt that cycle: the first builds a fresh level, the second shows the death screen.
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TurnState {
+    AwaitingInput,
+    PlayerTurn,
+    MonsterTurn,
+    NewTurn,
+    NextLevel,
+    GameOver,
+}